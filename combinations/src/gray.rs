@@ -0,0 +1,113 @@
+use crate::{binomial, Stepper};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The element that left a combination and the one that entered it.
+pub type Swap<T> = (T, T);
+
+// Moves increasing positions below n, followed by n as a sentinel, to the
+// next combination in revolving door order (Knuth's Algorithm R). Returns the
+// positions that left and entered it, together with the first of the two
+// changed slots.
+fn next_revolving_door(c: &mut [usize], n: usize) -> Option<(usize, usize, usize)> {
+    let k = c.len() - 1;
+    if k == 0 || k == n {
+        return None;
+    }
+
+    // c[j - 1] here is c_j in Knuth's 1-based notation.
+    let mut decrease = if k % 2 == 1 {
+        if c[0] + 1 < c[1] {
+            c[0] += 1;
+            return Some((c[0] - 1, c[0], 0));
+        }
+        true
+    } else {
+        if c[0] > 0 {
+            c[0] -= 1;
+            return Some((c[0] + 1, c[0], 0));
+        }
+        false
+    };
+
+    let mut j = 2;
+    while j <= k {
+        if decrease && c[j - 1] >= j {
+            let out = c[j - 1];
+            c[j - 1] = c[j - 2];
+            c[j - 2] = j - 2;
+            return Some((out, j - 2, j - 2));
+        }
+        if !decrease && c[j - 1] + 1 < c[j] {
+            let out = c[j - 2];
+            c[j - 2] = c[j - 1];
+            c[j - 1] += 1;
+            return Some((out, c[j - 1], j - 2));
+        }
+        decrease = !decrease;
+        j += 1;
+    }
+    None
+}
+
+// The k-subsets of `arr` in revolving door order: each combination differs
+// from the previous one by exactly one element leaving and one entering.
+// Elements of a combination are kept in position order.
+pub struct RevolvingDoor<'a, T> {
+    arr: &'a [T],
+    // Increasing positions, followed by `arr.len()` as a sentinel.
+    indices: Vec<usize>,
+    combination: Vec<T>,
+    stepper: Stepper,
+}
+
+impl<'a, T: Clone> RevolvingDoor<'a, T> {
+    pub fn new(arr: &'a [T], k: usize) -> Self {
+        let mut indices = (0..k).collect::<Vec<_>>();
+        indices.push(arr.len());
+        let combination = if k <= arr.len() {
+            arr[..k].to_vec()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            arr,
+            indices,
+            combination,
+            stepper: Stepper::new(binomial(arr.len(), k)),
+        }
+    }
+
+    // There is no swap before the first combination.
+    pub fn next_slice(&mut self) -> Option<(&[T], Option<Swap<&T>>)> {
+        let n = self.arr.len();
+        let change = self
+            .stepper
+            .step(None, || next_revolving_door(&mut self.indices, n).map(Some))?;
+
+        let change = change.map(|(out, into, first)| {
+            let k = self.combination.len();
+            for slot in first..(first + 2).min(k) {
+                self.combination[slot] = self.arr[self.indices[slot]].clone();
+            }
+            (&self.arr[out], &self.arr[into])
+        });
+        Some((&self.combination, change))
+    }
+}
+
+impl<'a, T: Clone> Iterator for RevolvingDoor<'a, T> {
+    type Item = (Vec<T>, Option<Swap<T>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_slice().map(|(combination, change)| {
+            let change = change.map(|(out, into)| (out.clone(), into.clone()));
+            (combination.to_vec(), change)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stepper.size_hint()
+    }
+}
@@ -1,27 +1,227 @@
 #![forbid(unsafe_code)]
 
-fn generate_combinations(
-    arr: &[i32],
+mod gray;
+mod multiset;
+mod parallel;
+mod permutations;
+mod product;
+mod rank;
+mod sample;
+mod subsets;
+
+pub use gray::{RevolvingDoor, Swap};
+pub use multiset::{distinct_combinations, DistinctCombinations};
+pub use parallel::ParCombinations;
+pub use permutations::{next_permutation, KPermutations, LexicographicPermutations, Permutations};
+pub use product::{mixed_radix_rank, mixed_radix_unrank, CartesianProduct, Dimensions, Product};
+pub use rank::{binomial_big, rank, unrank};
+pub use sample::{reservoir_sample, sample_combination, sample_combinations, sample_positions};
+pub use subsets::{CombinationsWithReplacement, PowerSet};
+
+use std::ops::Add;
+
+// `feasible` sees every partial combination right after an element is added;
+// returning false skips it along with everything that extends it.
+fn generate_combinations<T, P, V>(
+    arr: &[T],
     start: usize,
     idx: usize,
     k: usize,
-    combination: &mut Vec<i32>,
-    result: &mut Vec<Vec<i32>>,
-) {
+    combination: &mut Vec<T>,
+    feasible: &mut P,
+    visit: &mut V,
+) where
+    T: Clone,
+    P: FnMut(&[T]) -> bool,
+    V: FnMut(&[T]),
+{
     if idx == k {
-        result.push(combination.clone());
+        visit(combination);
         return;
     }
     for i in start..arr.len() {
-        combination[idx] = arr[i];
-        generate_combinations(arr, i + 1, idx + 1, k, combination, result);
+        combination.push(arr[i].clone());
+        if feasible(combination) {
+            generate_combinations(arr, i + 1, idx + 1, k, combination, feasible, visit);
+        }
+        combination.pop();
     }
 }
 
-pub fn combinations(arr: &[i32], k: usize) -> Vec<Vec<i32>> {
+pub fn combinations<T: Clone>(arr: &[T], k: usize) -> Vec<Vec<T>> {
+    combinations_pruned(arr, k, |_| true)
+}
+
+// Calls `visit` with every combination whose partial combinations all pass
+// `feasible`, without collecting them.
+pub fn search_combinations<T, P, V>(arr: &[T], k: usize, mut feasible: P, mut visit: V)
+where
+    T: Clone,
+    P: FnMut(&[T]) -> bool,
+    V: FnMut(&[T]),
+{
+    let mut combination = Vec::with_capacity(k);
+
+    generate_combinations(arr, 0, 0, k, &mut combination, &mut feasible, &mut visit);
+}
+
+pub fn combinations_pruned<T, P>(arr: &[T], k: usize, feasible: P) -> Vec<Vec<T>>
+where
+    T: Clone,
+    P: FnMut(&[T]) -> bool,
+{
     let mut result = Vec::new();
-    let mut combination = vec![0; k];
 
-    generate_combinations(arr, 0, 0, k, &mut combination, &mut result);
+    search_combinations(arr, k, feasible, |combination| {
+        result.push(combination.to_vec())
+    });
     result
 }
+
+// Combinations whose total weight is at most `budget`. Weights must not be
+// negative, since a branch is cut as soon as its partial sum is over budget.
+pub fn combinations_within_budget<T, W, F>(arr: &[T], k: usize, weight: F, budget: W) -> Vec<Vec<T>>
+where
+    T: Clone,
+    W: Copy + Default + Add<Output = W> + PartialOrd,
+    F: Fn(&T) -> W,
+{
+    combinations_pruned(arr, k, |partial| {
+        let total = partial
+            .iter()
+            .fold(W::default(), |total, item| total + weight(item));
+        total <= budget
+    })
+}
+
+// Same as `combinations`, but refers to the elements instead of cloning them.
+pub fn combinations_ref<T>(arr: &[T], k: usize) -> Vec<Vec<&T>> {
+    combinations(&arr.iter().collect::<Vec<_>>(), k)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// C(n, k), or `None` if it does not fit in a `usize`.
+pub fn binomial(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+
+    let k = k.min(n - k);
+    let mut result: u128 = 1;
+    for i in 0..k {
+        // Exact at every step: the product of i + 1 consecutive numbers is
+        // divisible by (i + 1)!.
+        result = result.checked_mul((n - i) as u128)? / (i + 1) as u128;
+    }
+    result.try_into().ok()
+}
+
+// Moves `indices` to the next k-subset of 0..n in lexicographic order and
+// returns the first position that changed, or `None` after the last one.
+fn next_combination(indices: &mut [usize], n: usize) -> Option<usize> {
+    let k = indices.len();
+    // The rightmost position that can still move right.
+    let i = (0..k).rev().find(|&i| indices[i] < n - k + i)?;
+    indices[i] += 1;
+    for j in i + 1..k {
+        indices[j] = indices[j - 1] + 1;
+    }
+    Some(i)
+}
+
+// Replaces the elements from position `from` on with the ones at `indices`.
+fn refill<T: Clone>(items: &mut Vec<T>, arr: &[T], indices: &[usize], from: usize) {
+    items.truncate(from);
+    items.extend(indices[from..].iter().map(|&i| arr[i].clone()));
+}
+
+// Counts down the items of a lazy iterator. The first item needs no step;
+// every later one comes from `advance`, which says what changed and returns
+// `None` after the last item.
+struct Stepper {
+    started: bool,
+    // `None` once the count no longer fits in a `usize`.
+    remaining: Option<usize>,
+}
+
+impl Stepper {
+    fn new(remaining: Option<usize>) -> Self {
+        Self {
+            started: false,
+            remaining,
+        }
+    }
+
+    fn step<C>(&mut self, first: C, advance: impl FnOnce() -> Option<C>) -> Option<C> {
+        if self.remaining == Some(0) {
+            return None;
+        }
+
+        let change = if !self.started {
+            self.started = true;
+            first
+        } else {
+            match advance() {
+                Some(change) => change,
+                None => {
+                    self.remaining = Some(0);
+                    return None;
+                }
+            }
+        };
+
+        self.remaining = self.remaining.map(|remaining| remaining - 1);
+        Some(change)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.remaining {
+            Some(remaining) => (remaining, Some(remaining)),
+            None => (usize::MAX, None),
+        }
+    }
+}
+
+// Yields the k-subsets of `arr` in lexicographic order of positions, keeping
+// only the current positions and elements.
+pub struct Combinations<'a, T> {
+    arr: &'a [T],
+    indices: Vec<usize>,
+    combination: Vec<T>,
+    stepper: Stepper,
+}
+
+impl<'a, T: Clone> Combinations<'a, T> {
+    pub fn new(arr: &'a [T], k: usize) -> Self {
+        Self {
+            arr,
+            indices: (0..k).collect(),
+            combination: Vec::with_capacity(k),
+            stepper: Stepper::new(binomial(arr.len(), k)),
+        }
+    }
+
+    // Like `next`, but lends the combination instead of allocating a new one.
+    pub fn next_slice(&mut self) -> Option<&[T]> {
+        let n = self.arr.len();
+        let changed = self
+            .stepper
+            .step(0, || next_combination(&mut self.indices, n))?;
+
+        refill(&mut self.combination, self.arr, &self.indices, changed);
+        Some(&self.combination)
+    }
+}
+
+impl<'a, T: Clone> Iterator for Combinations<'a, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_slice().map(|combination| combination.to_vec())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stepper.size_hint()
+    }
+}
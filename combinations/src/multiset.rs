@@ -0,0 +1,122 @@
+use crate::{refill, Stepper};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Equal elements are adjacent in `sorted`, so skipping a candidate equal to
+// the previous one at the same depth skips every repeated combination.
+fn generate_distinct_combinations<T: Clone + Eq>(
+    sorted: &[T],
+    start: usize,
+    k: usize,
+    combination: &mut Vec<T>,
+    result: &mut Vec<Vec<T>>,
+) {
+    if combination.len() == k {
+        result.push(combination.clone());
+        return;
+    }
+    for i in start..sorted.len() {
+        if i > start && sorted[i] == sorted[i - 1] {
+            continue;
+        }
+        combination.push(sorted[i].clone());
+        generate_distinct_combinations(sorted, i + 1, k, combination, result);
+        combination.pop();
+    }
+}
+
+// Treats `arr` as a multiset: [1, 1, 2] has the 2-combinations [1, 1] and
+// [1, 2], each once. Combinations are sorted and come in lexicographic order.
+pub fn distinct_combinations<T: Ord + Clone>(arr: &[T], k: usize) -> Vec<Vec<T>> {
+    let mut sorted = arr.to_vec();
+    sorted.sort();
+
+    let mut result = Vec::new();
+    let mut combination = Vec::with_capacity(k);
+    generate_distinct_combinations(&sorted, 0, k, &mut combination, &mut result);
+    result
+}
+
+// The number of distinct k-combinations of a sorted multiset.
+fn count_distinct<T: Eq>(sorted: &[T], k: usize) -> Option<usize> {
+    // ways[s] is the number of ways to pick s elements from the groups of
+    // equal elements seen so far.
+    let mut ways = vec![0usize; k + 1];
+    ways[0] = 1;
+    for group in sorted.chunk_by(|a, b| a == b) {
+        for s in (1..=k).rev() {
+            for taken in 1..=group.len().min(s) {
+                ways[s] = ways[s].checked_add(ways[s - taken])?;
+            }
+        }
+    }
+    Some(ways[k])
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Moves the rightmost position that can take a greater value to the first
+// copy of the next value in `sorted`, packs the following positions after it
+// and returns the first position that changed.
+fn next_distinct<T: Eq>(indices: &mut [usize], sorted: &[T]) -> Option<usize> {
+    let (n, k) = (sorted.len(), indices.len());
+    for i in (0..k).rev() {
+        let current = &sorted[indices[i]];
+        let next = (indices[i] + 1..n).find(|&next| sorted[next] != *current);
+        match next {
+            Some(next) if next + (k - 1 - i) < n => {
+                for (offset, j) in (i..k).enumerate() {
+                    indices[j] = next + offset;
+                }
+                return Some(i);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// The lazy version of `distinct_combinations`. Each combination is kept as
+// positions in the sorted elements, always using the first copies of every
+// value, so it has exactly one representation.
+pub struct DistinctCombinations<T> {
+    sorted: Vec<T>,
+    indices: Vec<usize>,
+    combination: Vec<T>,
+    stepper: Stepper,
+}
+
+impl<T: Ord + Clone> DistinctCombinations<T> {
+    pub fn new(arr: &[T], k: usize) -> Self {
+        let mut sorted = arr.to_vec();
+        sorted.sort();
+
+        Self {
+            stepper: Stepper::new(count_distinct(&sorted, k)),
+            sorted,
+            indices: (0..k).collect(),
+            combination: Vec::with_capacity(k),
+        }
+    }
+
+    pub fn next_slice(&mut self) -> Option<&[T]> {
+        let changed = self
+            .stepper
+            .step(0, || next_distinct(&mut self.indices, &self.sorted))?;
+
+        refill(&mut self.combination, &self.sorted, &self.indices, changed);
+        Some(&self.combination)
+    }
+}
+
+impl<T: Ord + Clone> Iterator for DistinctCombinations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_slice().map(|combination| combination.to_vec())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stepper.size_hint()
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{binomial_big, Combinations};
+use num_bigint::BigUint;
+use rayon::iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer};
+use rayon::iter::ParallelIterator;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The k-subsets of `arr` as a rayon parallel iterator. The range of ranks is
+// halved whenever rayon wants more work, and each half starts by unranking
+// its first combination, so there is no shared state between threads.
+pub struct ParCombinations<'a, T> {
+    producer: RankRange<'a, T>,
+}
+
+impl<'a, T: Clone + Send + Sync> ParCombinations<'a, T> {
+    pub fn new(arr: &'a [T], k: usize) -> Self {
+        Self {
+            producer: RankRange {
+                arr,
+                k,
+                start: BigUint::ZERO,
+                end: binomial_big(arr.len(), k),
+            },
+        }
+    }
+}
+
+impl<'a, T: Clone + Send + Sync> ParallelIterator for ParCombinations<'a, T> {
+    type Item = Vec<T>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge_unindexed(self.producer, consumer)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+struct RankRange<'a, T> {
+    arr: &'a [T],
+    k: usize,
+    start: BigUint,
+    end: BigUint,
+}
+
+impl<'a, T: Clone + Send + Sync> UnindexedProducer for RankRange<'a, T> {
+    type Item = Vec<T>;
+
+    fn split(self) -> (Self, Option<Self>) {
+        if &self.end - &self.start < BigUint::from(2u32) {
+            return (self, None);
+        }
+
+        let middle = (&self.start + &self.end) / 2u32;
+        let right = RankRange {
+            arr: self.arr,
+            k: self.k,
+            start: middle.clone(),
+            end: self.end,
+        };
+        let left = RankRange {
+            end: middle,
+            ..self
+        };
+        (left, Some(right))
+    }
+
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        // A range too long for a `usize` could not be finished anyway.
+        let len = usize::try_from(&self.end - &self.start).unwrap_or(usize::MAX);
+        folder.consume_iter(Combinations::from_rank(self.arr, self.k, &self.start).take(len))
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{refill, Stepper};
+
+////////////////////////////////////////////////////////////////////////////////
+
+fn factorial(n: usize) -> Option<usize> {
+    (1..=n).try_fold(1usize, |result, i| result.checked_mul(i))
+}
+
+// n! / (m1! * m2! * ...) for the runs of equal elements in a sorted slice.
+fn distinct_permutations<T: Eq>(sorted: &[T]) -> Option<usize> {
+    let mut result = 1usize;
+    let mut run = 0;
+    for (i, item) in sorted.iter().enumerate() {
+        run = if i > 0 && sorted[i - 1] == *item {
+            run + 1
+        } else {
+            1
+        };
+        result = result.checked_mul(i + 1)? / run;
+    }
+    Some(result)
+}
+
+// Rearranges `arr` into the next greater permutation and returns true, or
+// returns false and sorts it if it already was the greatest one.
+pub fn next_permutation<T: Ord>(arr: &mut [T]) -> bool {
+    let i = match (1..arr.len()).rev().find(|&i| arr[i - 1] < arr[i]) {
+        None => {
+            arr.reverse();
+            return false;
+        }
+        Some(i) => i,
+    };
+
+    let j = (i..arr.len()).rev().find(|&j| arr[i - 1] < arr[j]).unwrap();
+    arr.swap(i - 1, j);
+    arr[i..].reverse();
+    true
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// One step of Heap's algorithm: swaps two items, or returns `None` after the
+// last ordering.
+fn next_heap_permutation<T>(items: &mut [T], counters: &mut [usize], i: &mut usize) -> Option<()> {
+    while *i < items.len() {
+        if counters[*i] < *i {
+            let j = if i.is_multiple_of(2) { 0 } else { counters[*i] };
+            items.swap(j, *i);
+            counters[*i] += 1;
+            *i = 1;
+            return Some(());
+        }
+        counters[*i] = 0;
+        *i += 1;
+    }
+    None
+}
+
+// All n! orderings by Heap's algorithm: each one differs from the previous
+// by a single swap.
+pub struct Permutations<T> {
+    items: Vec<T>,
+    counters: Vec<usize>,
+    i: usize,
+    stepper: Stepper,
+}
+
+impl<T: Clone> Permutations<T> {
+    pub fn new(arr: &[T]) -> Self {
+        Self {
+            items: arr.to_vec(),
+            counters: vec![0; arr.len()],
+            i: 1,
+            stepper: Stepper::new(factorial(arr.len())),
+        }
+    }
+
+    pub fn next_slice(&mut self) -> Option<&[T]> {
+        self.stepper.step((), || {
+            next_heap_permutation(&mut self.items, &mut self.counters, &mut self.i)
+        })?;
+        Some(&self.items)
+    }
+}
+
+impl<T: Clone> Iterator for Permutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_slice().map(|permutation| permutation.to_vec())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stepper.size_hint()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The distinct orderings of the elements in lexicographic order, starting
+// from the sorted one. Equal elements are not told apart, so [1, 1, 2] has
+// three permutations.
+pub struct LexicographicPermutations<T> {
+    items: Vec<T>,
+    stepper: Stepper,
+}
+
+impl<T: Ord + Clone> LexicographicPermutations<T> {
+    pub fn new(arr: &[T]) -> Self {
+        let mut items = arr.to_vec();
+        items.sort();
+        Self {
+            stepper: Stepper::new(distinct_permutations(&items)),
+            items,
+        }
+    }
+
+    pub fn next_slice(&mut self) -> Option<&[T]> {
+        self.stepper
+            .step((), || next_permutation(&mut self.items).then_some(()))?;
+        Some(&self.items)
+    }
+}
+
+impl<T: Ord + Clone> Iterator for LexicographicPermutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_slice().map(|permutation| permutation.to_vec())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stepper.size_hint()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Moves `indices` to the next ordered selection of distinct positions below
+// `used.len()` and returns the first position that changed. `used` marks the
+// positions in the selection.
+fn next_k_permutation(indices: &mut [usize], used: &mut [bool]) -> Option<usize> {
+    let (n, k) = (used.len(), indices.len());
+    for i in (0..k).rev() {
+        used[indices[i]] = false;
+        let next = (indices[i] + 1..n).find(|&next| !used[next]);
+        if let Some(next) = next {
+            indices[i] = next;
+            used[next] = true;
+            for index in &mut indices[i + 1..] {
+                let smallest = (0..n).find(|&smallest| !used[smallest]).unwrap();
+                *index = smallest;
+                used[smallest] = true;
+            }
+            return Some(i);
+        }
+    }
+    None
+}
+
+// Ordered selections of k distinct positions, in lexicographic order of
+// positions.
+pub struct KPermutations<'a, T> {
+    arr: &'a [T],
+    indices: Vec<usize>,
+    used: Vec<bool>,
+    permutation: Vec<T>,
+    stepper: Stepper,
+}
+
+impl<'a, T: Clone> KPermutations<'a, T> {
+    pub fn new(arr: &'a [T], k: usize) -> Self {
+        let n = arr.len();
+        let remaining = if k > n {
+            Some(0)
+        } else {
+            (n - k + 1..=n).try_fold(1usize, |result, i| result.checked_mul(i))
+        };
+
+        Self {
+            arr,
+            indices: (0..k).collect(),
+            used: (0..n).map(|i| i < k).collect(),
+            permutation: Vec::with_capacity(k),
+            stepper: Stepper::new(remaining),
+        }
+    }
+
+    pub fn next_slice(&mut self) -> Option<&[T]> {
+        let changed = self
+            .stepper
+            .step(0, || next_k_permutation(&mut self.indices, &mut self.used))?;
+
+        refill(&mut self.permutation, self.arr, &self.indices, changed);
+        Some(&self.permutation)
+    }
+}
+
+impl<'a, T: Clone> Iterator for KPermutations<'a, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_slice().map(|permutation| permutation.to_vec())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stepper.size_hint()
+    }
+}
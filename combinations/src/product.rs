@@ -0,0 +1,264 @@
+use crate::Stepper;
+use num_bigint::BigUint;
+use rand::Rng;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The position of `digits` among all digit vectors below `radices`, with the
+// last digit changing fastest, or `None` if a digit is out of range.
+pub fn mixed_radix_rank(radices: &[usize], digits: &[usize]) -> Option<BigUint> {
+    if radices.len() != digits.len() {
+        return None;
+    }
+    radices
+        .iter()
+        .zip(digits)
+        .try_fold(BigUint::ZERO, |rank, (&radix, &digit)| {
+            (digit < radix).then(|| rank * radix + digit)
+        })
+}
+
+// The digits at position `rank`, or `None` if there are not that many.
+pub fn mixed_radix_unrank(rank: &BigUint, radices: &[usize]) -> Option<Vec<usize>> {
+    let total = radices
+        .iter()
+        .fold(BigUint::from(1u32), |total, &radix| total * radix);
+    if *rank >= total {
+        return None;
+    }
+
+    let mut rank = rank.clone();
+    let mut digits = vec![0; radices.len()];
+    for (digit, &radix) in digits.iter_mut().zip(radices).rev() {
+        let radix = BigUint::from(radix);
+        *digit = usize::try_from(&rank % &radix).unwrap();
+        rank /= radix;
+    }
+    Some(digits)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Moves `digits` to the next vector below `radices` like an odometer and
+// returns the first digit that changed.
+fn next_digits(digits: &mut [usize], radices: &[usize]) -> Option<usize> {
+    let i = (0..digits.len())
+        .rev()
+        .find(|&i| digits[i] + 1 < radices[i])?;
+    digits[i] += 1;
+    digits[i + 1..].fill(0);
+    Some(i)
+}
+
+// Steps through every digit vector below `radices`.
+struct Odometer {
+    radices: Vec<usize>,
+    digits: Vec<usize>,
+    stepper: Stepper,
+}
+
+impl Odometer {
+    fn new(radices: Vec<usize>) -> Self {
+        // A zero radix leaves nothing to step through, even if the product of
+        // the others overflows.
+        let remaining = if radices.contains(&0) {
+            Some(0)
+        } else {
+            radices
+                .iter()
+                .try_fold(1usize, |total, &radix| total.checked_mul(radix))
+        };
+        Self {
+            digits: vec![0; radices.len()],
+            radices,
+            stepper: Stepper::new(remaining),
+        }
+    }
+
+    fn from_rank(radices: Vec<usize>, rank: &BigUint) -> Self {
+        let mut odometer = Self::new(radices);
+        match mixed_radix_unrank(rank, &odometer.radices) {
+            None => odometer.stepper.remaining = Some(0),
+            Some(digits) => {
+                odometer.stepper.remaining = odometer
+                    .stepper
+                    .remaining
+                    .map(|remaining| remaining - usize::try_from(rank).unwrap());
+                odometer.digits = digits;
+            }
+        }
+        odometer
+    }
+
+    // Returns the first digit that changed.
+    fn step(&mut self) -> Option<usize> {
+        let (digits, radices) = (&mut self.digits, &self.radices);
+        self.stepper.step(0, || next_digits(digits, radices))
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Vec<usize>> {
+        if self.radices.contains(&0) {
+            return None;
+        }
+        Some(
+            self.radices
+                .iter()
+                .map(|&radix| rng.gen_range(0..radix))
+                .collect(),
+        )
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Every way to pick one element from each slice, in lexicographic order of
+// positions.
+pub struct CartesianProduct<'a, T> {
+    slices: Vec<&'a [T]>,
+    odometer: Odometer,
+    tuple: Vec<T>,
+}
+
+impl<'a, T: Clone> CartesianProduct<'a, T> {
+    pub fn new(slices: &[&'a [T]]) -> Self {
+        Self::with_odometer(slices, Odometer::new(radices(slices)))
+    }
+
+    pub fn from_rank(slices: &[&'a [T]], rank: &BigUint) -> Self {
+        Self::with_odometer(slices, Odometer::from_rank(radices(slices), rank))
+    }
+
+    fn with_odometer(slices: &[&'a [T]], odometer: Odometer) -> Self {
+        Self {
+            slices: slices.to_vec(),
+            tuple: Vec::with_capacity(slices.len()),
+            odometer,
+        }
+    }
+
+    // The positions of the last tuple returned.
+    pub fn digits(&self) -> &[usize] {
+        &self.odometer.digits
+    }
+
+    // A uniformly random tuple, independent of the iteration.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Vec<T>> {
+        let digits = self.odometer.sample(rng)?;
+        Some(
+            digits
+                .iter()
+                .zip(&self.slices)
+                .map(|(&digit, slice)| slice[digit].clone())
+                .collect(),
+        )
+    }
+
+    pub fn next_slice(&mut self) -> Option<&[T]> {
+        let changed = self.odometer.step()?;
+        self.tuple.truncate(changed);
+        let digits = &self.odometer.digits[changed..];
+        self.tuple.extend(
+            digits
+                .iter()
+                .zip(&self.slices[changed..])
+                .map(|(&digit, slice)| slice[digit].clone()),
+        );
+        Some(&self.tuple)
+    }
+}
+
+fn radices<T>(slices: &[&[T]]) -> Vec<usize> {
+    slices.iter().map(|slice| slice.len()).collect()
+}
+
+impl<'a, T: Clone> Iterator for CartesianProduct<'a, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_slice().map(|tuple| tuple.to_vec())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.odometer.stepper.size_hint()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A tuple of slices of possibly different element types.
+pub trait Dimensions {
+    type Item;
+
+    fn radices(&self) -> Vec<usize>;
+    fn get(&self, digits: &[usize]) -> Self::Item;
+}
+
+macro_rules! impl_dimensions {
+    ($($T:ident $i:tt),+) => {
+        impl<'a, $($T: Clone),+> Dimensions for ($(&'a [$T],)+) {
+            type Item = ($($T,)+);
+
+            fn radices(&self) -> Vec<usize> {
+                vec![$(self.$i.len()),+]
+            }
+
+            fn get(&self, digits: &[usize]) -> Self::Item {
+                ($(self.$i[digits[$i]].clone(),)+)
+            }
+        }
+    };
+}
+
+impl_dimensions!(A 0);
+impl_dimensions!(A 0, B 1);
+impl_dimensions!(A 0, B 1, C 2);
+impl_dimensions!(A 0, B 1, C 2, D 3);
+impl_dimensions!(A 0, B 1, C 2, D 3, E 4);
+impl_dimensions!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_dimensions!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_dimensions!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+// The same as `CartesianProduct`, but yields tuples, so every slice can hold
+// a different type: `Product::new((&sizes[..], &names[..]))`.
+pub struct Product<S: Dimensions> {
+    dimensions: S,
+    odometer: Odometer,
+}
+
+impl<S: Dimensions> Product<S> {
+    pub fn new(dimensions: S) -> Self {
+        Self {
+            odometer: Odometer::new(dimensions.radices()),
+            dimensions,
+        }
+    }
+
+    pub fn from_rank(dimensions: S, rank: &BigUint) -> Self {
+        Self {
+            odometer: Odometer::from_rank(dimensions.radices(), rank),
+            dimensions,
+        }
+    }
+
+    pub fn digits(&self) -> &[usize] {
+        &self.odometer.digits
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<S::Item> {
+        let digits = self.odometer.sample(rng)?;
+        Some(self.dimensions.get(&digits))
+    }
+}
+
+impl<S: Dimensions> Iterator for Product<S> {
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.odometer.step()?;
+        Some(self.dimensions.get(&self.odometer.digits))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.odometer.stepper.size_hint()
+    }
+}
@@ -0,0 +1,80 @@
+use crate::Combinations;
+use num_bigint::BigUint;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// C(n, k) without overflow.
+pub fn binomial_big(n: usize, k: usize) -> BigUint {
+    if k > n {
+        return BigUint::ZERO;
+    }
+
+    let k = k.min(n - k);
+    let mut result = BigUint::from(1u32);
+    for i in 0..k {
+        result = result * (n - i) / (i + 1);
+    }
+    result
+}
+
+// The position of a k-subset of 0..n, given as increasing indices, in the
+// lexicographic order used by `Combinations`, or `None` if the indices are
+// not increasing or not below n.
+pub fn rank(indices: &[usize], n: usize) -> Option<BigUint> {
+    let k = indices.len();
+    let increasing = indices.windows(2).all(|pair| pair[0] < pair[1]);
+    if !increasing || indices.iter().any(|&index| index >= n) {
+        return None;
+    }
+
+    // Every subset after this one is counted by exactly one of the terms.
+    let after = indices
+        .iter()
+        .enumerate()
+        .map(|(i, &index)| binomial_big(n - 1 - index, k - i))
+        .sum::<BigUint>();
+    Some(binomial_big(n, k) - 1u32 - after)
+}
+
+// The k-subset of 0..n at position `rank` in lexicographic order, or `None`
+// if there are not that many.
+pub fn unrank(rank: &BigUint, n: usize, k: usize) -> Option<Vec<usize>> {
+    if *rank >= binomial_big(n, k) {
+        return None;
+    }
+
+    let mut rank = rank.clone();
+    let mut indices = Vec::with_capacity(k);
+    let mut index = 0;
+    for i in 0..k {
+        let rest = k - 1 - i;
+        // The number of subsets with `index` at position i.
+        let mut count = binomial_big(n - 1 - index, rest);
+        while rank >= count {
+            rank -= &count;
+            let m = n - 1 - index;
+            count = count * (m - rest) / m;
+            index += 1;
+        }
+        indices.push(index);
+        index += 1;
+    }
+    Some(indices)
+}
+
+impl<'a, T: Clone> Combinations<'a, T> {
+    // Starts at the combination with the given rank, so that the space can be
+    // split into ranges; yields nothing if `rank` is past the end.
+    pub fn from_rank(arr: &'a [T], k: usize, rank: &BigUint) -> Self {
+        let n = arr.len();
+        let mut combinations = Self::new(arr, k);
+        match unrank(rank, n, k) {
+            None => combinations.stepper.remaining = Some(0),
+            Some(indices) => {
+                combinations.indices = indices;
+                combinations.stepper.remaining = usize::try_from(binomial_big(n, k) - rank).ok();
+            }
+        }
+        combinations
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{binomial_big, unrank};
+use num_bigint::BigUint;
+use rand::Rng;
+use std::collections::BTreeSet;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A uniformly random k-subset of 0..n by Floyd's algorithm, in increasing
+// order, using k random numbers and O(k) memory.
+pub fn sample_positions<R: Rng + ?Sized>(n: usize, k: usize, rng: &mut R) -> Option<Vec<usize>> {
+    if k > n {
+        return None;
+    }
+
+    let mut chosen = BTreeSet::new();
+    for j in n - k..n {
+        let t = rng.gen_range(0..=j);
+        if !chosen.insert(t) {
+            chosen.insert(j);
+        }
+    }
+    Some(chosen.into_iter().collect())
+}
+
+// A uniformly random k-combination of `arr`, with elements in their order in
+// `arr`, or `None` if `arr` is too short.
+pub fn sample_combination<T: Clone, R: Rng + ?Sized>(
+    arr: &[T],
+    k: usize,
+    rng: &mut R,
+) -> Option<Vec<T>> {
+    let positions = sample_positions(arr.len(), k, rng)?;
+    Some(positions.into_iter().map(|i| arr[i].clone()).collect())
+}
+
+// A uniformly random k-subset of a stream of unknown length (Algorithm R),
+// in no particular order. Shorter streams are returned whole.
+pub fn reservoir_sample<T, I, R>(items: I, k: usize, rng: &mut R) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+    R: Rng + ?Sized,
+{
+    let mut reservoir = Vec::with_capacity(k);
+    for (i, item) in items.into_iter().enumerate() {
+        if i < k {
+            reservoir.push(item);
+        } else {
+            let j = rng.gen_range(0..=i);
+            if j < k {
+                reservoir[j] = item;
+            }
+        }
+    }
+    reservoir
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Uniform in 0..bound by rejection, which takes fewer than two tries on average.
+fn random_below<R: Rng + ?Sized>(bound: &BigUint, rng: &mut R) -> BigUint {
+    assert!(*bound > BigUint::ZERO);
+
+    let bits = bound.bits();
+    let mut bytes = vec![0u8; bits.div_ceil(8) as usize];
+    loop {
+        rng.fill_bytes(&mut bytes);
+        *bytes.last_mut().unwrap() >>= bytes.len() as u64 * 8 - bits;
+        let value = BigUint::from_bytes_le(&bytes);
+        if value < *bound {
+            return value;
+        }
+    }
+}
+
+// m different k-combinations of `arr`, each set of m equally likely, in
+// lexicographic order. Floyd's algorithm picks their ranks, so the cost does
+// not depend on the number of combinations. Returns `None` if there are
+// fewer than m of them.
+pub fn sample_combinations<T: Clone, R: Rng + ?Sized>(
+    arr: &[T],
+    k: usize,
+    m: usize,
+    rng: &mut R,
+) -> Option<Vec<Vec<T>>> {
+    let total = binomial_big(arr.len(), k);
+    if BigUint::from(m) > total {
+        return None;
+    }
+
+    let mut ranks = BTreeSet::new();
+    let mut j = &total - m;
+    while j < total {
+        let t = random_below(&(&j + 1u32), rng);
+        if ranks.contains(&t) {
+            ranks.insert(j.clone());
+        } else {
+            ranks.insert(t);
+        }
+        j += 1u32;
+    }
+
+    let combinations = ranks
+        .iter()
+        .map(|rank| {
+            let positions = unrank(rank, arr.len(), k).unwrap();
+            positions.into_iter().map(|i| arr[i].clone()).collect()
+        })
+        .collect();
+    Some(combinations)
+}
@@ -0,0 +1,117 @@
+use crate::{binomial, next_combination, refill, Stepper};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Moves non-decreasing `indices` below n to the next multiset in
+// lexicographic order and returns the first position that changed.
+fn next_multiset(indices: &mut [usize], n: usize) -> Option<usize> {
+    let i = indices.iter().rposition(|&index| index + 1 < n)?;
+    indices[i] += 1;
+    let index = indices[i];
+    indices[i + 1..].fill(index);
+    Some(i)
+}
+
+// Multisets of k elements drawn from `arr` with repetition, as non-decreasing
+// positions in lexicographic order.
+pub struct CombinationsWithReplacement<'a, T> {
+    arr: &'a [T],
+    indices: Vec<usize>,
+    combination: Vec<T>,
+    stepper: Stepper,
+}
+
+impl<'a, T: Clone> CombinationsWithReplacement<'a, T> {
+    pub fn new(arr: &'a [T], k: usize) -> Self {
+        let n = arr.len();
+        let remaining = match (n, k) {
+            (0, 0) => Some(1),
+            (0, _) => Some(0),
+            _ => binomial(n + k - 1, k),
+        };
+
+        Self {
+            arr,
+            indices: vec![0; k],
+            combination: Vec::with_capacity(k),
+            stepper: Stepper::new(remaining),
+        }
+    }
+
+    pub fn next_slice(&mut self) -> Option<&[T]> {
+        let n = self.arr.len();
+        let changed = self
+            .stepper
+            .step(0, || next_multiset(&mut self.indices, n))?;
+
+        refill(&mut self.combination, self.arr, &self.indices, changed);
+        Some(&self.combination)
+    }
+}
+
+impl<'a, T: Clone> Iterator for CombinationsWithReplacement<'a, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_slice().map(|combination| combination.to_vec())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stepper.size_hint()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// All 2^n subsets, from the empty one up to the whole slice; subsets of the
+// same size come in lexicographic order of positions.
+pub struct PowerSet<'a, T> {
+    arr: &'a [T],
+    indices: Vec<usize>,
+    subset: Vec<T>,
+    stepper: Stepper,
+}
+
+impl<'a, T: Clone> PowerSet<'a, T> {
+    pub fn new(arr: &'a [T]) -> Self {
+        let remaining = u32::try_from(arr.len())
+            .ok()
+            .and_then(|n| 1usize.checked_shl(n));
+
+        Self {
+            arr,
+            indices: Vec::new(),
+            subset: Vec::with_capacity(arr.len()),
+            stepper: Stepper::new(remaining),
+        }
+    }
+
+    pub fn next_slice(&mut self) -> Option<&[T]> {
+        let n = self.arr.len();
+        let indices = &mut self.indices;
+        let changed = self.stepper.step(0, || {
+            // After the last subset of a size comes the first one of the next.
+            next_combination(indices, n).or_else(|| {
+                (indices.len() < n).then(|| {
+                    *indices = (0..indices.len() + 1).collect();
+                    0
+                })
+            })
+        })?;
+
+        refill(&mut self.subset, self.arr, &self.indices, changed);
+        Some(&self.subset)
+    }
+}
+
+impl<'a, T: Clone> Iterator for PowerSet<'a, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_slice().map(|subset| subset.to_vec())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stepper.size_hint()
+    }
+}
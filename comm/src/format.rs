@@ -0,0 +1,229 @@
+use crate::{Entry, Membership, Options, Record, Totals};
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    // Tab-indented columns like GNU comm.
+    #[default]
+    Text,
+    // One JSON object per line. Byte records that are not valid UTF-8 are
+    // converted lossily, since JSON strings can only hold text.
+    JsonLines,
+    // RFC 4180 CSV with a header row.
+    Csv,
+}
+
+fn side<T>(entry: &Entry<T>) -> &'static str {
+    match entry {
+        Entry::OnlyLeft(_) => "left",
+        Entry::OnlyRight(_) => "right",
+        Entry::Both(_) => "both",
+        Entry::Changed(..) => "changed",
+    }
+}
+
+fn json_string(value: &[u8]) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in String::from_utf8_lossy(value).chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn csv_field(value: &[u8]) -> Cow<'_, [u8]> {
+    if !value
+        .iter()
+        .any(|byte| matches!(byte, b',' | b'"' | b'\r' | b'\n'))
+    {
+        return Cow::Borrowed(value);
+    }
+
+    let mut out = Vec::with_capacity(value.len() + 2);
+    out.push(b'"');
+    for &byte in value {
+        if byte == b'"' {
+            out.push(b'"');
+        }
+        out.push(byte);
+    }
+    out.push(b'"');
+    Cow::Owned(out)
+}
+
+impl Format {
+    // Near-match output gets a third CSV column for the right line of a
+    // changed pair, left empty on every other row.
+    pub(crate) fn write_entries_header<W: Write>(
+        &self,
+        out: &mut W,
+        options: &Options,
+    ) -> io::Result<()> {
+        match (self, options.near_match) {
+            (Format::Csv, None) => out.write_all(b"side,line\n"),
+            (Format::Csv, Some(_)) => out.write_all(b"side,line,right\n"),
+            (Format::Text | Format::JsonLines, _) => Ok(()),
+        }
+    }
+
+    pub(crate) fn write_entry<W: Write, T: Record>(
+        &self,
+        out: &mut W,
+        entry: &Entry<T>,
+        options: &Options,
+    ) -> io::Result<()> {
+        let line = entry.line().as_bytes();
+        let right = match entry {
+            Entry::Changed(_, right) => Some(right.as_bytes()),
+            _ => None,
+        };
+
+        match self {
+            Format::Text => {
+                let indent = options
+                    .output_delimiter
+                    .repeat(options.indent(entry.column()));
+                out.write_all(indent.as_bytes())?;
+                out.write_all(line)?;
+                if let Some(right) = right {
+                    out.write_all(options.output_delimiter.as_bytes())?;
+                    out.write_all(right)?;
+                }
+                out.write_all(&[options.separator])
+            }
+            Format::JsonLines => match right {
+                None => writeln!(
+                    out,
+                    "{{\"side\":\"{}\",\"line\":{}}}",
+                    side(entry),
+                    json_string(line)
+                ),
+                Some(right) => writeln!(
+                    out,
+                    "{{\"side\":\"{}\",\"line\":{},\"right\":{}}}",
+                    side(entry),
+                    json_string(line),
+                    json_string(right)
+                ),
+            },
+            Format::Csv => {
+                write!(out, "{},", side(entry))?;
+                out.write_all(&csv_field(line))?;
+                if options.near_match.is_some() {
+                    out.write_all(b",")?;
+                    out.write_all(&csv_field(right.unwrap_or_default()))?;
+                }
+                out.write_all(b"\n")
+            }
+        }
+    }
+
+    pub(crate) fn write_totals<W: Write>(
+        &self,
+        out: &mut W,
+        totals: &Totals,
+        options: &Options,
+    ) -> io::Result<()> {
+        // The changed count only appears when near matches are reported.
+        let changed = options.near_match.map(|_| totals.changed);
+
+        match self {
+            Format::Text => {
+                let delimiter = &options.output_delimiter;
+                write!(
+                    out,
+                    "{}{}{}{}{}{}",
+                    totals.only_left,
+                    delimiter,
+                    totals.only_right,
+                    delimiter,
+                    totals.both,
+                    delimiter
+                )?;
+                if let Some(changed) = changed {
+                    write!(out, "{}{}", changed, delimiter)?;
+                }
+                out.write_all(b"total")?;
+                out.write_all(&[options.separator])
+            }
+            Format::JsonLines => {
+                write!(
+                    out,
+                    "{{\"total\":{{\"left\":{},\"right\":{},\"both\":{}",
+                    totals.only_left, totals.only_right, totals.both
+                )?;
+                if let Some(changed) = changed {
+                    write!(out, ",\"changed\":{}", changed)?;
+                }
+                writeln!(out, "}}}}")
+            }
+            // The totals share the columns of the entries.
+            Format::Csv => {
+                let padding = if changed.is_some() { "," } else { "" };
+                write!(
+                    out,
+                    "left total,{}{}\nright total,{}{}\nboth total,{}{}\n",
+                    totals.only_left, padding, totals.only_right, padding, totals.both, padding
+                )?;
+                if let Some(changed) = changed {
+                    writeln!(out, "changed total,{},", changed)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn write_memberships_header<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        match self {
+            Format::Csv => out.write_all(b"files,line\n"),
+            Format::Text | Format::JsonLines => Ok(()),
+        }
+    }
+
+    pub(crate) fn write_membership<W: Write, T: Record>(
+        &self,
+        out: &mut W,
+        membership: &Membership<T>,
+        options: &Options,
+    ) -> io::Result<()> {
+        let files = membership
+            .files
+            .iter()
+            .map(|file| file.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let line = membership.line.as_bytes();
+
+        match self {
+            Format::Text => {
+                write!(out, "{}{}", files, options.output_delimiter)?;
+                out.write_all(line)?;
+                out.write_all(&[options.separator])
+            }
+            Format::JsonLines => writeln!(
+                out,
+                "{{\"files\":[{}],\"line\":{}}}",
+                files,
+                json_string(line)
+            ),
+            Format::Csv => {
+                out.write_all(&csv_field(files.as_bytes()))?;
+                out.write_all(b",")?;
+                out.write_all(&csv_field(line))?;
+                out.write_all(b"\n")
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::CommError;
+use regex::bytes::Regex;
+use std::borrow::Cow;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Default)]
+pub enum Field {
+    #[default]
+    Whole,
+    // 1-based, inclusive field numbers like in `cut -f start-end`.
+    Range {
+        delimiter: char,
+        start: usize,
+        end: Option<usize>,
+    },
+    // The first capture group if the pattern has one, otherwise the whole match.
+    Regex(Regex),
+}
+
+impl Field {
+    pub fn range(delimiter: char, start: usize, end: Option<usize>) -> Result<Self, CommError> {
+        if start == 0 || end == Some(0) {
+            return Err(CommError::FieldNumber);
+        }
+        if let Some(end) = end.filter(|end| *end < start) {
+            return Err(CommError::DecreasingFields { start, end });
+        }
+        Ok(Field::Range {
+            delimiter,
+            start,
+            end,
+        })
+    }
+
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Field::Regex(Regex::new(pattern)?))
+    }
+
+    fn extract<'a>(&self, record: &'a [u8]) -> &'a [u8] {
+        match self {
+            Field::Whole => record,
+            Field::Range {
+                delimiter,
+                start,
+                end,
+            } => {
+                let mut buffer = [0; 4];
+                let delimiter = delimiter.encode_utf8(&mut buffer).as_bytes();
+                let mut bounds = record
+                    .windows(delimiter.len())
+                    .enumerate()
+                    .filter(|(_, window)| *window == delimiter)
+                    .map(|(i, _)| (i, i + delimiter.len()));
+
+                let mut from = 0;
+                for _ in 1..*start {
+                    match bounds.next() {
+                        None => return &[],
+                        Some((_, next)) => from = next,
+                    }
+                }
+
+                let to = match end {
+                    None => record.len(),
+                    Some(end) => bounds
+                        .nth(end.saturating_sub(*start))
+                        .map_or(record.len(), |(i, _)| i),
+                };
+
+                &record[from..to.max(from)]
+            }
+            Field::Regex(regex) => match regex.captures(record) {
+                None => record,
+                Some(captures) => captures
+                    .get(1)
+                    .or_else(|| captures.get(0))
+                    .map_or(record, |m| m.as_bytes()),
+            },
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Default)]
+pub struct Key {
+    pub field: Field,
+    pub ignore_case: bool,
+    pub ignore_whitespace: bool,
+}
+
+impl Key {
+    pub fn is_whole_line(&self) -> bool {
+        matches!(self.field, Field::Whole) && !self.ignore_case && !self.ignore_whitespace
+    }
+
+    pub fn extract<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let mut key = String::from_utf8_lossy(self.field.extract(line.as_bytes()));
+
+        if self.ignore_whitespace {
+            key = Cow::Owned(key.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        if self.ignore_case {
+            key = Cow::Owned(key.to_lowercase());
+        }
+
+        key
+    }
+
+    // Byte records have no encoding, so only ASCII case and whitespace are folded.
+    pub fn extract_bytes<'a>(&self, record: &'a [u8]) -> Cow<'a, [u8]> {
+        let mut key = Cow::Borrowed(self.field.extract(record));
+
+        if self.ignore_whitespace {
+            key = Cow::Owned(
+                key.split(u8::is_ascii_whitespace)
+                    .filter(|word| !word.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(&b' '),
+            );
+        }
+        if self.ignore_case {
+            key = Cow::Owned(key.to_ascii_lowercase());
+        }
+
+        key
+    }
+}
@@ -1,26 +1,629 @@
 #![forbid(unsafe_code)]
 
-use std::collections::HashSet;
-use std::{fs::File, io::BufRead, io::BufReader};
+mod format;
+mod key;
+mod many;
+mod near;
+mod partition;
+mod record;
 
-pub fn comm(first_filename: &str, second_filename: &str) {
-    let first_file = File::open(first_filename).unwrap();
-    let second_file = File::open(second_filename).unwrap();
+pub use format::Format;
+pub use key::{Field, Key};
+pub use many::{
+    comm_many, comm_many_sorted, write_memberships, HashedMany, Membership, SetOperation,
+    SortedMany,
+};
+pub use near::{NearMatches, Similarity};
+pub use partition::{comm_partitioned, PartitionedComm, Partitioning};
+pub use record::Record;
 
-    let first_reader = BufReader::new(first_file);
-    let second_reader = BufReader::new(second_file);
+use flate2::bufread::MultiGzDecoder;
+use record::Records;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::{fs::File, mem};
+use thiserror::Error;
+use xz2::bufread::XzDecoder;
 
-    let mut lines: HashSet<String> = HashSet::new();
+////////////////////////////////////////////////////////////////////////////////
 
-    for line in first_reader.lines() {
-        lines.insert(line.unwrap());
+#[derive(Error, Debug)]
+pub enum CommError {
+    #[error("{path}: {source}")]
+    Open { path: String, source: io::Error },
+    #[error("file {file}: {source}")]
+    Read { file: usize, source: io::Error },
+    #[error("file {file}, line {line}: invalid UTF-8")]
+    InvalidUtf8 { file: usize, line: usize },
+    #[error("file {file} is not in sorted order")]
+    Unsorted { file: usize },
+    #[error("fields are numbered from 1")]
+    FieldNumber,
+    #[error("field range {start}-{end} is decreasing")]
+    DecreasingFields { start: usize, end: usize },
+    #[error(transparent)]
+    Write(io::Error),
+    #[error("temporary file: {0}")]
+    Temp(io::Error),
+    #[error("at least one partition is required")]
+    NoBuckets,
+}
+
+// `-` stands for the standard input, like in most command-line tools.
+// Compressed files are decompressed on the fly.
+pub fn open(path: &str) -> Result<Box<dyn BufRead>, CommError> {
+    let opened = if path == "-" {
+        decompress(BufReader::new(io::stdin()))
+    } else {
+        File::open(path).and_then(|file| decompress(BufReader::new(file)))
+    };
+
+    opened.map_err(|source| CommError::Open {
+        path: path.to_string(),
+        source,
+    })
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+
+// Detects gzip, zstd and xz by their magic bytes rather than the file name.
+// Concatenated streams, as produced by appending to a compressed log, are
+// read to the end. Anything else is passed through unchanged.
+pub fn decompress<'a, R: BufRead + 'a>(mut reader: R) -> io::Result<Box<dyn BufRead + 'a>> {
+    // A pipe can hand out fewer bytes at a time than the longest magic number,
+    // so read all of it and put it back in front of the rest.
+    let mut magic = Vec::with_capacity(XZ_MAGIC.len());
+    reader
+        .by_ref()
+        .take(XZ_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    let reader = io::Cursor::new(magic.clone()).chain(reader);
+
+    if magic.starts_with(GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        Ok(Box::new(BufReader::new(zstd::Decoder::with_buffer(
+            reader,
+        )?)))
+    } else if magic.starts_with(XZ_MAGIC) {
+        Ok(Box::new(BufReader::new(XzDecoder::new_multi_decoder(
+            reader,
+        ))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    First,
+    Second,
+    Common,
+    Changed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Entry<T = String> {
+    OnlyLeft(T),
+    OnlyRight(T),
+    Both(T),
+    // A left line and the right line it was paired with by `NearMatches`.
+    Changed(T, T),
+}
+
+impl<T> Entry<T> {
+    pub fn column(&self) -> Column {
+        match self {
+            Entry::OnlyLeft(_) => Column::First,
+            Entry::OnlyRight(_) => Column::Second,
+            Entry::Both(_) => Column::Common,
+            Entry::Changed(..) => Column::Changed,
+        }
+    }
+
+    // The left line of a changed pair.
+    pub fn line(&self) -> &T {
+        match self {
+            Entry::OnlyLeft(line) | Entry::OnlyRight(line) | Entry::Both(line) => line,
+            Entry::Changed(line, _) => line,
+        }
+    }
+
+    pub fn into_line(self) -> T {
+        match self {
+            Entry::OnlyLeft(line) | Entry::OnlyRight(line) | Entry::Both(line) => line,
+            Entry::Changed(line, _) => line,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub suppress_first: bool,
+    pub suppress_second: bool,
+    pub suppress_common: bool,
+    pub check_order: bool,
+    // Pair repeated lines one by one, like GNU comm, instead of treating
+    // each input as a set.
+    pub multiset: bool,
+    pub total: bool,
+    pub key: Key,
+    pub separator: u8,
+    pub strip_cr: bool,
+    pub output_delimiter: String,
+    pub format: Format,
+    // Pairing near matches needs every entry at once, so it keeps the whole
+    // output in memory, even for sorted input.
+    pub near_match: Option<Similarity>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            suppress_first: false,
+            suppress_second: false,
+            suppress_common: false,
+            check_order: false,
+            multiset: false,
+            total: false,
+            key: Key::default(),
+            separator: b'\n',
+            strip_cr: false,
+            output_delimiter: "\t".to_string(),
+            format: Format::Text,
+            near_match: None,
+        }
+    }
+}
+
+impl Options {
+    pub fn only_common() -> Self {
+        Self {
+            suppress_first: true,
+            suppress_second: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn zero_terminated() -> Self {
+        Self {
+            separator: b'\0',
+            ..Self::default()
+        }
+    }
+
+    pub fn is_suppressed(&self, column: Column) -> bool {
+        match column {
+            Column::First => self.suppress_first,
+            Column::Second => self.suppress_second,
+            Column::Common => self.suppress_common,
+            Column::Changed => false,
+        }
+    }
+
+    // Each column is shifted right by one delimiter for every visible column before it.
+    fn indent(&self, column: Column) -> usize {
+        let before: &[Column] = match column {
+            Column::First => &[],
+            Column::Second => &[Column::First],
+            Column::Common => &[Column::First, Column::Second],
+            Column::Changed => &[Column::First, Column::Second, Column::Common],
+        };
+
+        before.iter().filter(|c| !self.is_suppressed(**c)).count()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub only_left: usize,
+    pub only_right: usize,
+    pub both: usize,
+    pub changed: usize,
+}
+
+impl Totals {
+    pub fn add<T>(&mut self, entry: &Entry<T>) {
+        match entry {
+            Entry::OnlyLeft(_) => self.only_left += 1,
+            Entry::OnlyRight(_) => self.only_right += 1,
+            Entry::Both(_) => self.both += 1,
+            Entry::Changed(..) => self.changed += 1,
+        }
+    }
+}
+
+// Pairs near matches first if `options.near_match` is set.
+pub fn write_entries<W, T, I>(
+    out: &mut W,
+    entries: I,
+    options: &Options,
+) -> Result<Totals, CommError>
+where
+    W: Write,
+    T: Record,
+    I: IntoIterator<Item = Result<Entry<T>, CommError>>,
+{
+    match options.near_match {
+        None => write_all_entries(out, entries, options),
+        Some(similarity) => {
+            let entries = NearMatches::new(entries.into_iter(), similarity, &options.key);
+            write_all_entries(out, entries, options)
+        }
+    }
+}
+
+fn write_all_entries<W, T, I>(
+    out: &mut W,
+    entries: I,
+    options: &Options,
+) -> Result<Totals, CommError>
+where
+    W: Write,
+    T: Record,
+    I: IntoIterator<Item = Result<Entry<T>, CommError>>,
+{
+    let format = options.format;
+    format
+        .write_entries_header(out, options)
+        .map_err(CommError::Write)?;
+
+    let mut totals = Totals::default();
+    for entry in entries {
+        let entry = entry?;
+        totals.add(&entry);
+
+        if !options.is_suppressed(entry.column()) {
+            format
+                .write_entry(out, &entry, options)
+                .map_err(CommError::Write)?;
+        }
+    }
+
+    if options.total {
+        format
+            .write_totals(out, &totals, options)
+            .map_err(CommError::Write)?;
+    }
+
+    out.flush().map_err(CommError::Write)?;
+    Ok(totals)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+struct Side<R: BufRead, T: Record> {
+    records: Records<R>,
+    head: Option<T>,
+    multiset: bool,
+    // A failed read, reported after the head before it has been used.
+    error: Option<CommError>,
+}
+
+impl<R: BufRead, T: Record> Side<R, T> {
+    fn new(reader: R, file: usize, options: &Options) -> Self {
+        Self {
+            records: Records::new(reader, file, options),
+            head: None,
+            multiset: options.multiset,
+            error: None,
+        }
+    }
+
+    // Sets keep only the first record of a run with equal keys.
+    fn read_next(&mut self, key: &Key, check_order: bool) -> Result<Option<T>, CommError> {
+        let mut next: Option<T> = self.records.read()?;
+        while !self.multiset
+            && matches!((&self.head, &next), (Some(previous), Some(next)) if next.key(key) == previous.key(key))
+        {
+            next = self.records.read()?;
+        }
+        if let (true, Some(previous), Some(next)) = (check_order, &self.head, &next) {
+            if next.key(key) < previous.key(key) {
+                return Err(CommError::Unsorted {
+                    file: self.records.file,
+                });
+            }
+        }
+
+        Ok(next)
+    }
+
+    // Replaces the head with the next record and returns the old one. If the
+    // read fails, the side is left without a head and `check` reports it.
+    fn advance(&mut self, key: &Key, check_order: bool) -> Option<T> {
+        let next = self.read_next(key, check_order).unwrap_or_else(|err| {
+            self.error = Some(err);
+            None
+        });
+        mem::replace(&mut self.head, next)
+    }
+
+    fn check(&mut self) -> Result<(), CommError> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+}
+
+pub struct SortedComm<L: BufRead, R: BufRead, T: Record = String> {
+    left: Side<L, T>,
+    right: Side<R, T>,
+    key: Key,
+    check_order: bool,
+    started: bool,
+    failed: bool,
+}
+
+impl<L: BufRead, R: BufRead> SortedComm<L, R> {
+    pub fn new(left: L, right: R, options: &Options) -> Self {
+        Self::with_records(left, right, options)
+    }
+}
+
+impl<L: BufRead, R: BufRead> SortedComm<L, R, Vec<u8>> {
+    pub fn new_bytes(left: L, right: R, options: &Options) -> Self {
+        Self::with_records(left, right, options)
+    }
+}
+
+impl<L: BufRead, R: BufRead, T: Record> SortedComm<L, R, T> {
+    pub fn with_records(left: L, right: R, options: &Options) -> Self {
+        Self {
+            left: Side::new(left, 1, options),
+            right: Side::new(right, 2, options),
+            key: options.key.clone(),
+            check_order: options.check_order,
+            started: false,
+            failed: false,
+        }
+    }
+
+    fn step(&mut self) -> Result<Option<Entry<T>>, CommError> {
+        let (key, check_order) = (&self.key, self.check_order);
+        if !self.started {
+            self.started = true;
+            self.left.advance(key, check_order);
+            self.right.advance(key, check_order);
+        }
+        self.left.check()?;
+        self.right.check()?;
+
+        let order = match (&self.left.head, &self.right.head) {
+            (None, None) => return Ok(None),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(left), Some(right)) => left.key(key).cmp(&right.key(key)),
+        };
+
+        let entry = match order {
+            Ordering::Less => Entry::OnlyLeft(self.left.advance(key, check_order).unwrap()),
+            Ordering::Greater => Entry::OnlyRight(self.right.advance(key, check_order).unwrap()),
+            Ordering::Equal => {
+                self.right.advance(key, check_order);
+                Entry::Both(self.left.advance(key, check_order).unwrap())
+            }
+        };
+
+        Ok(Some(entry))
+    }
+}
+
+impl<L: BufRead, R: BufRead, T: Record> Iterator for SortedComm<L, R, T> {
+    type Item = Result<Entry<T>, CommError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        match self.step() {
+            Ok(entry) => entry.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Pairs right records with left ones by key. The partitioned comm runs one
+// of these per bucket, so it must not depend on how records are read.
+struct Matcher<T: Record> {
+    // Left records that have not been matched yet, grouped by key and tagged
+    // with their position in the file. The record is `None` when it is equal
+    // to its key, so plain comparisons do not store every record twice.
+    left: HashMap<Vec<u8>, VecDeque<(usize, Option<T>)>>,
+    seen: HashSet<Vec<u8>>,
+    key: Key,
+    multiset: bool,
+}
+
+impl<T: Record> Matcher<T> {
+    fn new(options: &Options) -> Self {
+        Self {
+            left: HashMap::new(),
+            seen: HashSet::new(),
+            key: options.key.clone(),
+            multiset: options.multiset,
+        }
     }
 
-    for line in second_reader.lines() {
-        let value = line.unwrap();
-        if lines.contains(&value.clone()) {
-            println!("{}", value.clone());
-            lines.remove(&value);
+    fn insert_left(&mut self, position: usize, line: T) {
+        let (key, line) = match line.key(&self.key) {
+            Cow::Borrowed(key) if key.len() == line.as_bytes().len() => (key.to_vec(), None),
+            key => (key.into_owned(), Some(line)),
+        };
+
+        let unmatched = self.left.entry(key).or_default();
+        if self.multiset || unmatched.is_empty() {
+            unmatched.push_back((position, line));
+        }
+    }
+
+    // Returns `None` for a repeated right record when matching sets.
+    fn match_right(&mut self, line: T) -> Option<Entry<T>> {
+        let key = line.key(&self.key).into_owned();
+        if !self.multiset && !self.seen.insert(key.clone()) {
+            return None;
+        }
+
+        let unmatched = match self.left.get_mut(&key) {
+            None => return Some(Entry::OnlyRight(line)),
+            Some(unmatched) => unmatched,
+        };
+
+        let (_, left_line) = unmatched.pop_front().unwrap();
+        if unmatched.is_empty() {
+            self.left.remove(&key);
+        }
+        match left_line {
+            Some(left_line) => Some(Entry::Both(left_line)),
+            None => Some(Entry::Both(T::from_bytes(key).unwrap())),
         }
     }
+
+    fn into_leftover(self) -> Vec<(usize, T)> {
+        let mut leftover = Vec::new();
+        for (key, unmatched) in self.left {
+            for (position, line) in unmatched {
+                let line = match line {
+                    Some(line) => line,
+                    None => T::from_bytes(key.clone()).unwrap(),
+                };
+                leftover.push((position, line));
+            }
+        }
+
+        leftover.sort_unstable_by_key(|(position, _)| *position);
+        leftover
+    }
+}
+
+pub struct HashedComm<R: BufRead, T: Record = String> {
+    matcher: Option<Matcher<T>>,
+    right: Records<R>,
+    leftover: Option<std::vec::IntoIter<(usize, T)>>,
+    failed: bool,
+}
+
+impl<R: BufRead> HashedComm<R> {
+    pub fn new<L: BufRead>(left: L, right: R, options: &Options) -> Result<Self, CommError> {
+        Self::with_records(left, right, options)
+    }
+}
+
+impl<R: BufRead> HashedComm<R, Vec<u8>> {
+    pub fn new_bytes<L: BufRead>(left: L, right: R, options: &Options) -> Result<Self, CommError> {
+        Self::with_records(left, right, options)
+    }
+}
+
+impl<R: BufRead, T: Record> HashedComm<R, T> {
+    pub fn with_records<L: BufRead>(
+        left: L,
+        right: R,
+        options: &Options,
+    ) -> Result<Self, CommError> {
+        let mut records = Records::new(left, 1, options);
+        let mut matcher = Matcher::new(options);
+        let mut position = 0;
+        while let Some(line) = records.read::<T>()? {
+            matcher.insert_left(position, line);
+            position += 1;
+        }
+
+        Ok(Self {
+            matcher: Some(matcher),
+            right: Records::new(right, 2, options),
+            leftover: None,
+            failed: false,
+        })
+    }
+
+    fn step(&mut self) -> Result<Option<Entry<T>>, CommError> {
+        if let Some(leftover) = &mut self.leftover {
+            return Ok(leftover.next().map(|(_, line)| Entry::OnlyLeft(line)));
+        }
+
+        let matcher = self.matcher.as_mut().unwrap();
+        while let Some(line) = self.right.read::<T>()? {
+            if let Some(entry) = matcher.match_right(line) {
+                return Ok(Some(entry));
+            }
+        }
+
+        let leftover = self.matcher.take().unwrap().into_leftover();
+        self.leftover = Some(leftover.into_iter());
+        self.step()
+    }
+}
+
+impl<R: BufRead, T: Record> Iterator for HashedComm<R, T> {
+    type Item = Result<Entry<T>, CommError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        match self.step() {
+            Ok(entry) => entry.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn comm(first_filename: &str, second_filename: &str) -> Result<(), CommError> {
+    comm_with_options(first_filename, second_filename, &Options::only_common())?;
+    Ok(())
+}
+
+pub fn comm_with_options(
+    first_filename: &str,
+    second_filename: &str,
+    options: &Options,
+) -> Result<Totals, CommError> {
+    let entries = HashedComm::new(open(first_filename)?, open(second_filename)?, options)?;
+    write_entries(&mut io::stdout().lock(), entries, options)
+}
+
+pub fn comm_sorted(
+    first_filename: &str,
+    second_filename: &str,
+    options: &Options,
+) -> Result<Totals, CommError> {
+    let entries = SortedComm::new(open(first_filename)?, open(second_filename)?, options);
+    write_entries(&mut io::stdout().lock(), entries, options)
+}
+
+pub fn comm_bytes(
+    first_filename: &str,
+    second_filename: &str,
+    options: &Options,
+) -> Result<Totals, CommError> {
+    let entries = HashedComm::new_bytes(open(first_filename)?, open(second_filename)?, options)?;
+    write_entries(&mut io::stdout().lock(), entries, options)
+}
+
+pub fn comm_sorted_bytes(
+    first_filename: &str,
+    second_filename: &str,
+    options: &Options,
+) -> Result<Totals, CommError> {
+    let entries = SortedComm::new_bytes(open(first_filename)?, open(second_filename)?, options);
+    write_entries(&mut io::stdout().lock(), entries, options)
 }
@@ -0,0 +1,345 @@
+#![forbid(unsafe_code)]
+
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, ValueEnum};
+use comm::{
+    write_entries, write_memberships, CommError, Field, Format, HashedComm, HashedMany, Key,
+    Options, PartitionedComm, Partitioning, Record, SetOperation, Similarity, SortedComm,
+    SortedMany,
+};
+use std::io::{self, BufRead};
+use std::process::ExitCode;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Set {
+    Intersection,
+    Union,
+    Difference,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+    Text,
+    #[value(name = "jsonl")]
+    JsonLines,
+    Csv,
+}
+
+/// Compare files line by line.
+///
+/// With two files, prints three columns: lines only in FILE1, lines only in
+/// FILE2 and lines in both. With --set or --at-least, any number of files is
+/// accepted and each selected line is printed after the list of files it was
+/// found in. A FILE of `-` reads the standard input.
+#[derive(Parser, Debug)]
+#[command(name = "comm", version)]
+struct Cli {
+    /// Suppress lines unique to FILE1
+    #[arg(short = '1')]
+    suppress_first: bool,
+
+    /// Suppress lines unique to FILE2
+    #[arg(short = '2')]
+    suppress_second: bool,
+
+    /// Suppress lines that appear in both files
+    #[arg(short = '3')]
+    suppress_common: bool,
+
+    /// Fail if the input is not sorted
+    #[arg(long)]
+    check_order: bool,
+
+    /// Match lines through a hash table instead of merging sorted input
+    #[arg(short, long)]
+    unsorted: bool,
+
+    /// Pair repeated lines one by one instead of treating inputs as sets
+    #[arg(long, requires = "unsorted")]
+    multiset: bool,
+
+    /// Spill unsorted input into N hashed temporary files and match them in parallel (at most 512)
+    #[arg(long, value_name = "N", requires = "unsorted", value_parser = clap::value_parser!(u64).range(1..=512))]
+    partitions: Option<u64>,
+
+    /// Number of worker threads for --partitions (all cores by default)
+    #[arg(long, value_name = "N", requires = "partitions", value_parser = clap::value_parser!(u64).range(1..))]
+    threads: Option<u64>,
+
+    /// Print a summary line with the number of lines in each column
+    #[arg(long)]
+    total: bool,
+
+    /// Line delimiter is NUL, not newline
+    #[arg(short, long)]
+    zero_terminated: bool,
+
+    /// Use a custom single-byte line delimiter
+    #[arg(long, value_name = "BYTE", value_parser = parse_separator, conflicts_with = "zero_terminated")]
+    record_separator: Option<u8>,
+
+    /// Drop a carriage return before each line delimiter
+    #[arg(long)]
+    strip_cr: bool,
+
+    /// Separate columns with STR instead of a tab
+    #[arg(
+        long,
+        value_name = "STR",
+        default_value = "\t",
+        hide_default_value = true
+    )]
+    output_delimiter: String,
+
+    /// Print entries as plain columns, JSON Lines or CSV
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Compare raw bytes without requiring UTF-8 input
+    #[arg(long)]
+    bytes: bool,
+
+    /// Field delimiter used by --fields (tab by default)
+    #[arg(
+        short = 't',
+        long,
+        value_name = "CHAR",
+        default_value_t = '\t',
+        hide_default_value = true
+    )]
+    delimiter: char,
+
+    /// Compare only the fields N-M, N- or N
+    #[arg(short, long, value_name = "RANGE", value_parser = parse_fields)]
+    fields: Option<(usize, Option<usize>)>,
+
+    /// Compare only the first capture group (or the whole match) of PATTERN
+    #[arg(long, value_name = "PATTERN", conflicts_with = "fields")]
+    key_regex: Option<String>,
+
+    /// Ignore case when comparing
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// Ignore leading, trailing and repeated whitespace when comparing
+    #[arg(short = 'w', long)]
+    ignore_whitespace: bool,
+
+    /// Pair unique lines within N character edits and report them as changed
+    #[arg(long, value_name = "N")]
+    near_edits: Option<usize>,
+
+    /// Pair unique lines sharing at least RATIO of their words and report them as changed
+    #[arg(long, value_name = "RATIO", value_parser = parse_ratio, conflicts_with = "near_edits")]
+    near_tokens: Option<f64>,
+
+    /// Combine any number of files with a set operation
+    #[arg(long, value_enum)]
+    set: Option<Set>,
+
+    /// Select lines found in at least K of the files
+    #[arg(long, value_name = "K", conflicts_with = "set")]
+    at_least: Option<usize>,
+
+    #[arg(required = true, value_name = "FILE")]
+    files: Vec<String>,
+}
+
+fn parse_separator(value: &str) -> Result<u8, String> {
+    match value {
+        "\\0" => Ok(b'\0'),
+        "\\n" => Ok(b'\n'),
+        "\\t" => Ok(b'\t'),
+        _ if value.len() == 1 => Ok(value.as_bytes()[0]),
+        _ => Err("expected a single byte, \\0, \\n or \\t".to_string()),
+    }
+}
+
+fn parse_ratio(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(ratio),
+        _ => Err("expected a number from 0 to 1".to_string()),
+    }
+}
+
+fn parse_fields(value: &str) -> Result<(usize, Option<usize>), String> {
+    let parse = |number: &str| match number.parse::<usize>() {
+        Ok(0) | Err(_) => Err(format!("invalid field number '{}'", number)),
+        Ok(number) => Ok(number),
+    };
+
+    match value.split_once('-') {
+        None => Ok((parse(value)?, Some(parse(value)?))),
+        Some((start, "")) => Ok((parse(start)?, None)),
+        Some((start, end)) => Ok((parse(start)?, Some(parse(end)?))),
+    }
+}
+
+impl Cli {
+    fn operation(&self) -> Option<SetOperation> {
+        match (self.set, self.at_least) {
+            (Some(Set::Intersection), _) => Some(SetOperation::Intersection),
+            (Some(Set::Union), _) => Some(SetOperation::Union),
+            (Some(Set::Difference), _) => Some(SetOperation::Difference),
+            (None, Some(count)) => Some(SetOperation::AtLeast(count)),
+            (None, None) => None,
+        }
+    }
+
+    fn partitioning(&self) -> Option<Partitioning> {
+        let buckets = self.partitions? as usize;
+        let default = Partitioning::default();
+        Some(Partitioning {
+            buckets,
+            threads: self
+                .threads
+                .map_or(default.threads, |threads| threads as usize),
+        })
+    }
+
+    fn options(&self) -> Result<Options, clap::Error> {
+        let field = match (&self.fields, &self.key_regex) {
+            (Some((start, end)), _) => Field::range(self.delimiter, *start, *end)
+                .map_err(|err| Cli::command().error(ErrorKind::ValueValidation, err))?,
+            (None, Some(pattern)) => Field::regex(pattern)
+                .map_err(|err| Cli::command().error(ErrorKind::ValueValidation, err))?,
+            (None, None) => Field::Whole,
+        };
+
+        let separator = match (self.zero_terminated, self.record_separator) {
+            (true, _) => b'\0',
+            (false, Some(separator)) => separator,
+            (false, None) => b'\n',
+        };
+
+        Ok(Options {
+            suppress_first: self.suppress_first,
+            suppress_second: self.suppress_second,
+            suppress_common: self.suppress_common,
+            check_order: self.check_order,
+            // Sorted input pairs repeated lines one by one, like GNU comm.
+            multiset: self.multiset || !self.unsorted,
+            total: self.total,
+            key: Key {
+                field,
+                ignore_case: self.ignore_case,
+                ignore_whitespace: self.ignore_whitespace,
+            },
+            separator,
+            strip_cr: self.strip_cr,
+            output_delimiter: self.output_delimiter.clone(),
+            format: match self.format {
+                OutputFormat::Text => Format::Text,
+                OutputFormat::JsonLines => Format::JsonLines,
+                OutputFormat::Csv => Format::Csv,
+            },
+            near_match: match (self.near_edits, self.near_tokens) {
+                (Some(edits), _) => Some(Similarity::EditDistance(edits)),
+                (None, Some(ratio)) => Some(Similarity::Tokens(ratio)),
+                (None, None) => None,
+            },
+        })
+    }
+
+    fn validate(&self) -> Result<(), clap::Error> {
+        if self.partitions.is_some() && self.operation().is_some() {
+            return Err(Cli::command().error(
+                ErrorKind::ArgumentConflict,
+                "--partitions cannot be combined with --set or --at-least",
+            ));
+        }
+
+        if self.operation().is_none() && self.files.len() != 2 {
+            return Err(Cli::command().error(
+                ErrorKind::WrongNumberOfValues,
+                "exactly two files are required without --set or --at-least",
+            ));
+        }
+
+        if self.files.iter().filter(|file| *file == "-").count() > 1 {
+            return Err(Cli::command().error(
+                ErrorKind::ArgumentConflict,
+                "the standard input can only be read once",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+fn run_two<T: Record>(
+    mut inputs: Vec<Box<dyn BufRead>>,
+    cli: &Cli,
+    options: &Options,
+) -> Result<(), CommError> {
+    let right = inputs.pop().unwrap();
+    let left = inputs.pop().unwrap();
+    let out = &mut io::stdout().lock();
+
+    if let Some(partitioning) = cli.partitioning() {
+        let entries = PartitionedComm::<T>::with_records(left, right, options, &partitioning)?;
+        write_entries(out, entries, options)?;
+    } else if cli.unsorted {
+        let entries = HashedComm::<_, T>::with_records(left, right, options)?;
+        write_entries(out, entries, options)?;
+    } else {
+        let entries = SortedComm::<_, _, T>::with_records(left, right, options);
+        write_entries(out, entries, options)?;
+    }
+    Ok(())
+}
+
+fn run_many<T: Record>(
+    inputs: Vec<Box<dyn BufRead>>,
+    operation: SetOperation,
+    unsorted: bool,
+    options: &Options,
+) -> Result<(), CommError> {
+    let out = &mut io::stdout().lock();
+
+    if unsorted {
+        let memberships = HashedMany::<T>::with_records(inputs, operation, options)?;
+        write_memberships(out, memberships, options)?;
+    } else {
+        let memberships = SortedMany::<_, T>::with_records(inputs, operation, options);
+        write_memberships(out, memberships, options)?;
+    }
+    Ok(())
+}
+
+fn run(cli: &Cli, options: &Options) -> Result<(), CommError> {
+    let inputs = cli
+        .files
+        .iter()
+        .map(|file| comm::open(file))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match (cli.operation(), cli.bytes) {
+        (None, false) => run_two::<String>(inputs, cli, options),
+        (None, true) => run_two::<Vec<u8>>(inputs, cli, options),
+        (Some(operation), false) => run_many::<String>(inputs, operation, cli.unsorted, options),
+        (Some(operation), true) => run_many::<Vec<u8>>(inputs, operation, cli.unsorted, options),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let options = match cli.validate().and_then(|_| cli.options()) {
+        Ok(options) => options,
+        Err(err) => err.exit(),
+    };
+
+    match run(&cli, &options) {
+        Ok(()) => ExitCode::SUCCESS,
+        // The reader went away, as with `comm a b | head`, so stop quietly.
+        Err(CommError::Write(err)) if err.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("comm: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{open, CommError, Key, Options, Record, Records, Side};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::vec;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetOperation {
+    Intersection,
+    Union,
+    // Present in the first file and in none of the others.
+    Difference,
+    AtLeast(usize),
+}
+
+impl SetOperation {
+    fn accepts(&self, files: &[usize], inputs: usize) -> bool {
+        match *self {
+            SetOperation::Intersection => files.len() == inputs,
+            SetOperation::Union => true,
+            SetOperation::Difference => files == [1],
+            SetOperation::AtLeast(count) => files.len() >= count,
+        }
+    }
+}
+
+// `files` holds the 1-based numbers of the inputs the line was found in,
+// in increasing order. The line itself is taken from the first of them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Membership<T = String> {
+    pub line: T,
+    pub files: Vec<usize>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct SortedMany<R: BufRead, T: Record = String> {
+    sides: Vec<Side<R, T>>,
+    operation: SetOperation,
+    key: Key,
+    check_order: bool,
+    started: bool,
+    failed: bool,
+}
+
+impl<R: BufRead> SortedMany<R> {
+    pub fn new(inputs: Vec<R>, operation: SetOperation, options: &Options) -> Self {
+        Self::with_records(inputs, operation, options)
+    }
+}
+
+impl<R: BufRead> SortedMany<R, Vec<u8>> {
+    pub fn new_bytes(inputs: Vec<R>, operation: SetOperation, options: &Options) -> Self {
+        Self::with_records(inputs, operation, options)
+    }
+}
+
+impl<R: BufRead, T: Record> SortedMany<R, T> {
+    pub fn with_records(inputs: Vec<R>, operation: SetOperation, options: &Options) -> Self {
+        Self {
+            sides: inputs
+                .into_iter()
+                .enumerate()
+                .map(|(i, input)| Side::new(input, i + 1, options))
+                .collect(),
+            operation,
+            key: options.key.clone(),
+            check_order: options.check_order,
+            started: false,
+            failed: false,
+        }
+    }
+
+    fn smallest_key(&self) -> Option<Vec<u8>> {
+        self.sides
+            .iter()
+            .filter_map(|side| side.head.as_ref())
+            .map(|head| head.key(&self.key))
+            .min()
+            .map(|key| key.into_owned())
+    }
+
+    fn step(&mut self) -> Result<Option<Membership<T>>, CommError> {
+        let (key, check_order) = (&self.key, self.check_order);
+        if !self.started {
+            self.started = true;
+            for side in &mut self.sides {
+                side.advance(key, check_order);
+            }
+        }
+
+        let inputs = self.sides.len();
+        loop {
+            for side in &mut self.sides {
+                side.check()?;
+            }
+            let smallest = match self.smallest_key() {
+                None => return Ok(None),
+                Some(smallest) => smallest,
+            };
+
+            let mut files = Vec::new();
+            let mut line = None;
+            for (i, side) in self.sides.iter_mut().enumerate() {
+                while let Some(true) = side.head.as_ref().map(|head| *head.key(key) == *smallest) {
+                    let record = side.advance(key, check_order).unwrap();
+                    if files.last() != Some(&(i + 1)) {
+                        files.push(i + 1);
+                        line.get_or_insert(record);
+                    }
+                }
+            }
+
+            if self.operation.accepts(&files, inputs) {
+                return Ok(Some(Membership {
+                    line: line.unwrap(),
+                    files,
+                }));
+            }
+        }
+    }
+}
+
+impl<R: BufRead, T: Record> Iterator for SortedMany<R, T> {
+    type Item = Result<Membership<T>, CommError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        match self.step() {
+            Ok(membership) => membership.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Reads every input up front; lines come out in order of first appearance.
+pub struct HashedMany<T: Record = String> {
+    memberships: vec::IntoIter<Membership<T>>,
+}
+
+impl HashedMany {
+    pub fn new<R: BufRead>(
+        inputs: Vec<R>,
+        operation: SetOperation,
+        options: &Options,
+    ) -> Result<Self, CommError> {
+        Self::with_records(inputs, operation, options)
+    }
+}
+
+impl HashedMany<Vec<u8>> {
+    pub fn new_bytes<R: BufRead>(
+        inputs: Vec<R>,
+        operation: SetOperation,
+        options: &Options,
+    ) -> Result<Self, CommError> {
+        Self::with_records(inputs, operation, options)
+    }
+}
+
+impl<T: Record> HashedMany<T> {
+    pub fn with_records<R: BufRead>(
+        inputs: Vec<R>,
+        operation: SetOperation,
+        options: &Options,
+    ) -> Result<Self, CommError> {
+        let count = inputs.len();
+        let mut positions: HashMap<Vec<u8>, usize> = HashMap::new();
+        let mut memberships: Vec<Membership<T>> = Vec::new();
+
+        for (i, input) in inputs.into_iter().enumerate() {
+            let mut records = Records::new(input, i + 1, options);
+            while let Some(line) = records.read::<T>()? {
+                let key = line.key(&options.key).into_owned();
+                match positions.get(&key) {
+                    Some(&position) => {
+                        let files = &mut memberships[position].files;
+                        if files.last() != Some(&(i + 1)) {
+                            files.push(i + 1);
+                        }
+                    }
+                    None => {
+                        positions.insert(key, memberships.len());
+                        memberships.push(Membership {
+                            line,
+                            files: vec![i + 1],
+                        });
+                    }
+                }
+            }
+        }
+
+        memberships.retain(|membership| operation.accepts(&membership.files, count));
+        Ok(Self {
+            memberships: memberships.into_iter(),
+        })
+    }
+}
+
+impl<T: Record> Iterator for HashedMany<T> {
+    type Item = Result<Membership<T>, CommError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.memberships.next().map(Ok)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn write_memberships<W, T, I>(
+    out: &mut W,
+    memberships: I,
+    options: &Options,
+) -> Result<usize, CommError>
+where
+    W: Write,
+    T: Record,
+    I: IntoIterator<Item = Result<Membership<T>, CommError>>,
+{
+    let format = options.format;
+    format
+        .write_memberships_header(out)
+        .map_err(CommError::Write)?;
+
+    let mut count = 0;
+    for membership in memberships {
+        let membership = membership?;
+        count += 1;
+
+        format
+            .write_membership(out, &membership, options)
+            .map_err(CommError::Write)?;
+    }
+
+    out.flush().map_err(CommError::Write)?;
+    Ok(count)
+}
+
+fn open_all(filenames: &[&str]) -> Result<Vec<Box<dyn BufRead>>, CommError> {
+    filenames.iter().map(|filename| open(filename)).collect()
+}
+
+pub fn comm_many(
+    filenames: &[&str],
+    operation: SetOperation,
+    options: &Options,
+) -> Result<usize, CommError> {
+    let memberships = HashedMany::new(open_all(filenames)?, operation, options)?;
+    write_memberships(&mut io::stdout().lock(), memberships, options)
+}
+
+pub fn comm_many_sorted(
+    filenames: &[&str],
+    operation: SetOperation,
+    options: &Options,
+) -> Result<usize, CommError> {
+    let memberships = SortedMany::new(open_all(filenames)?, operation, options);
+    write_memberships(&mut io::stdout().lock(), memberships, options)
+}
@@ -0,0 +1,181 @@
+use crate::{CommError, Entry, Key, Record};
+use std::collections::HashSet;
+use std::vec;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Similarity {
+    // At most this many inserted, removed or replaced characters.
+    EditDistance(usize),
+    // At least this share of whitespace-separated tokens in common (0.0 to 1.0).
+    Tokens(f64),
+}
+
+impl Similarity {
+    // Returns how far apart the two keys are (lower is closer), or `None` if
+    // they are not within the threshold.
+    fn distance(&self, left: &str, right: &str) -> Option<f64> {
+        match *self {
+            Similarity::EditDistance(max) => edit_distance(left, right, max).map(|d| d as f64),
+            Similarity::Tokens(min) => {
+                let similarity = token_similarity(left, right);
+                (similarity >= min).then_some(1.0 - similarity)
+            }
+        }
+    }
+}
+
+fn edit_distance(left: &str, right: &str, max: usize) -> Option<usize> {
+    let left = left.chars().collect::<Vec<_>>();
+    let right = right.chars().collect::<Vec<_>>();
+    if left.len().abs_diff(right.len()) > max {
+        return None;
+    }
+
+    let mut previous = (0..=right.len()).collect::<Vec<_>>();
+    let mut current = vec![0; right.len() + 1];
+    for (i, l) in left.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, r) in right.iter().enumerate() {
+            let replace = previous[j] + usize::from(l != r);
+            current[j + 1] = replace.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().min().unwrap() > &max {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[right.len()]).filter(|distance| *distance <= max)
+}
+
+// Jaccard index of the two token sets.
+fn token_similarity(left: &str, right: &str) -> f64 {
+    let left = left.split_whitespace().collect::<HashSet<_>>();
+    let right = right.split_whitespace().collect::<HashSet<_>>();
+    if left.is_empty() && right.is_empty() {
+        return 1.0;
+    }
+
+    let common = left.intersection(&right).count();
+    common as f64 / (left.len() + right.len() - common) as f64
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Holds back every entry until the input ends. Then each left line is paired
+// with the closest right line within the threshold, closest pairs first, and
+// the entries are passed on in their original order, with each pair reported
+// as `Entry::Changed` in place of its left line. Every pair of unique lines is
+// compared, so this is quadratic in the number of differences.
+pub struct NearMatches<I, T: Record> {
+    entries: I,
+    similarity: Similarity,
+    key: Key,
+    buffered: Vec<Entry<T>>,
+    paired: Option<vec::IntoIter<Entry<T>>>,
+    failed: bool,
+}
+
+impl<I, T> NearMatches<I, T>
+where
+    T: Record,
+    I: Iterator<Item = Result<Entry<T>, CommError>>,
+{
+    pub fn new(entries: I, similarity: Similarity, key: &Key) -> Self {
+        Self {
+            entries,
+            similarity,
+            key: key.clone(),
+            buffered: Vec::new(),
+            paired: None,
+            failed: false,
+        }
+    }
+
+    fn pair(&mut self) -> Vec<Entry<T>> {
+        let keys = self
+            .buffered
+            .iter()
+            .map(|entry| match entry {
+                Entry::OnlyLeft(line) | Entry::OnlyRight(line) => {
+                    String::from_utf8_lossy(&line.key(&self.key)).into_owned()
+                }
+                _ => String::new(),
+            })
+            .collect::<Vec<_>>();
+        let side = |is_left: bool| {
+            self.buffered
+                .iter()
+                .enumerate()
+                .filter(move |(_, entry)| match entry {
+                    Entry::OnlyLeft(_) => is_left,
+                    Entry::OnlyRight(_) => !is_left,
+                    _ => false,
+                })
+                .map(|(i, _)| i)
+        };
+
+        let mut candidates = Vec::new();
+        for i in side(true) {
+            for j in side(false) {
+                if let Some(distance) = self.similarity.distance(&keys[i], &keys[j]) {
+                    candidates.push((distance, i, j));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+
+        let mut partner = vec![None; self.buffered.len()];
+        for (_, i, j) in candidates {
+            if partner[i].is_none() && partner[j].is_none() {
+                partner[i] = Some(j);
+                partner[j] = Some(i);
+            }
+        }
+
+        let mut buffered = self.buffered.drain(..).map(Some).collect::<Vec<_>>();
+        let mut paired = Vec::new();
+        for i in 0..buffered.len() {
+            match partner[i] {
+                None => paired.push(buffered[i].take().unwrap()),
+                Some(j) if matches!(buffered[i], Some(Entry::OnlyLeft(_))) => {
+                    let left = buffered[i].take().unwrap().into_line();
+                    let right = buffered[j].take().unwrap().into_line();
+                    paired.push(Entry::Changed(left, right));
+                }
+                // A paired right line is taken along with its left one.
+                Some(_) => {}
+            }
+        }
+        paired
+    }
+}
+
+impl<I, T> Iterator for NearMatches<I, T>
+where
+    T: Record,
+    I: Iterator<Item = Result<Entry<T>, CommError>>,
+{
+    type Item = Result<Entry<T>, CommError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        while self.paired.is_none() {
+            match self.entries.next() {
+                None => self.paired = Some(self.pair().into_iter()),
+                Some(Ok(entry)) => self.buffered.push(entry),
+                Some(Err(err)) => {
+                    self.failed = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        self.paired.as_mut().unwrap().next().map(Ok)
+    }
+}
@@ -0,0 +1,292 @@
+use crate::{open, write_entries, CommError, Entry, Matcher, Options, Record, Records, Totals};
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::sync::Mutex;
+use std::thread;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Every bucket keeps a temporary file open until its entries are merged, so
+// `buckets` has to stay below the limit on open files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Partitioning {
+    pub buckets: usize,
+    pub threads: usize,
+}
+
+impl Default for Partitioning {
+    fn default() -> Self {
+        Self {
+            buckets: 64,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Records are spilled to disk as a tag byte, the position of the record in
+// its input, the length and the raw bytes.
+const LEFT_INPUT: u8 = 0;
+const RIGHT_INPUT: u8 = 1;
+const ONLY_LEFT: u8 = 2;
+const ONLY_RIGHT: u8 = 3;
+const BOTH: u8 = 4;
+
+fn write_record<W: Write>(out: &mut W, tag: u8, position: usize, bytes: &[u8]) -> io::Result<()> {
+    out.write_all(&[tag])?;
+    out.write_all(&(position as u64).to_le_bytes())?;
+    out.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    out.write_all(bytes)
+}
+
+fn read_record<R: BufRead>(input: &mut R) -> io::Result<Option<(u8, usize, Vec<u8>)>> {
+    if input.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+
+    let mut tag = [0; 1];
+    let mut number = [0; 8];
+    input.read_exact(&mut tag)?;
+    input.read_exact(&mut number)?;
+    let position = u64::from_le_bytes(number) as usize;
+    input.read_exact(&mut number)?;
+    let mut bytes = vec![0; u64::from_le_bytes(number) as usize];
+    input.read_exact(&mut bytes)?;
+
+    Ok(Some((tag[0], position, bytes)))
+}
+
+fn temp_file() -> Result<BufWriter<File>, CommError> {
+    Ok(BufWriter::new(
+        tempfile::tempfile().map_err(CommError::Temp)?,
+    ))
+}
+
+fn rewind(writer: BufWriter<File>) -> Result<BufReader<File>, CommError> {
+    let mut file = writer
+        .into_inner()
+        .map_err(|err| CommError::Temp(err.into_error()))?;
+    file.seek(SeekFrom::Start(0)).map_err(CommError::Temp)?;
+    Ok(BufReader::new(file))
+}
+
+fn spill<R: BufRead, T: Record>(
+    input: R,
+    file: usize,
+    options: &Options,
+    buckets: &mut [BufWriter<File>],
+) -> Result<(), CommError> {
+    let tag = if file == 1 { LEFT_INPUT } else { RIGHT_INPUT };
+    let mut records = Records::new(input, file, options);
+    let mut position = 0;
+    while let Some(line) = records.read::<T>()? {
+        let mut hasher = DefaultHasher::new();
+        line.key(&options.key).hash(&mut hasher);
+        let bucket = (hasher.finish() % buckets.len() as u64) as usize;
+
+        write_record(&mut buckets[bucket], tag, position, line.as_bytes())
+            .map_err(CommError::Temp)?;
+        position += 1;
+    }
+
+    Ok(())
+}
+
+// Matches one bucket in memory, exactly like `HashedComm` does for whole
+// inputs, and writes the entries to a new temporary file. The left input is
+// spilled first, so its records come before the right ones.
+fn process_bucket<T: Record>(
+    bucket: BufWriter<File>,
+    options: &Options,
+) -> Result<BufReader<File>, CommError> {
+    let mut matcher = Matcher::<T>::new(options);
+    let mut input = rewind(bucket)?;
+    let mut out = temp_file()?;
+    while let Some((tag, position, bytes)) = read_record(&mut input).map_err(CommError::Temp)? {
+        let line = T::from_bytes(bytes).unwrap();
+        if tag == LEFT_INPUT {
+            matcher.insert_left(position, line);
+            continue;
+        }
+
+        let written = match matcher.match_right(line) {
+            None => Ok(()),
+            Some(Entry::Both(line)) => write_record(&mut out, BOTH, position, line.as_bytes()),
+            Some(Entry::OnlyRight(line)) => {
+                write_record(&mut out, ONLY_RIGHT, position, line.as_bytes())
+            }
+            Some(Entry::OnlyLeft(_) | Entry::Changed(..)) => unreachable!(),
+        };
+        written.map_err(CommError::Temp)?;
+    }
+
+    for (position, line) in matcher.into_leftover() {
+        write_record(&mut out, ONLY_LEFT, position, line.as_bytes()).map_err(CommError::Temp)?;
+    }
+
+    rewind(out)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Produces the same entries in the same order as `HashedComm`, but only keeps
+// one bucket per worker thread in memory at a time.
+pub struct PartitionedComm<T: Record = String> {
+    results: Vec<BufReader<File>>,
+    // Entries from the right input come first, in their order, followed by
+    // the unmatched left ones in theirs.
+    heads: BinaryHeap<Reverse<(bool, usize, usize)>>,
+    pending: Vec<Option<Entry<T>>>,
+    failed: bool,
+}
+
+impl PartitionedComm {
+    pub fn new<L: BufRead, R: BufRead>(
+        left: L,
+        right: R,
+        options: &Options,
+        partitioning: &Partitioning,
+    ) -> Result<Self, CommError> {
+        Self::with_records(left, right, options, partitioning)
+    }
+}
+
+impl PartitionedComm<Vec<u8>> {
+    pub fn new_bytes<L: BufRead, R: BufRead>(
+        left: L,
+        right: R,
+        options: &Options,
+        partitioning: &Partitioning,
+    ) -> Result<Self, CommError> {
+        Self::with_records(left, right, options, partitioning)
+    }
+}
+
+impl<T: Record> PartitionedComm<T> {
+    pub fn with_records<L: BufRead, R: BufRead>(
+        left: L,
+        right: R,
+        options: &Options,
+        partitioning: &Partitioning,
+    ) -> Result<Self, CommError> {
+        if partitioning.buckets == 0 {
+            return Err(CommError::NoBuckets);
+        }
+
+        let mut buckets = (0..partitioning.buckets)
+            .map(|_| temp_file())
+            .collect::<Result<Vec<_>, _>>()?;
+        spill::<_, T>(left, 1, options, &mut buckets)?;
+        spill::<_, T>(right, 2, options, &mut buckets)?;
+
+        let queue = Mutex::new(buckets.into_iter().enumerate().collect::<Vec<_>>());
+
+        let processed = thread::scope(|scope| {
+            let workers = (0..partitioning.threads.max(1))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut processed = Vec::new();
+                        loop {
+                            let next = queue.lock().unwrap().pop();
+                            match next {
+                                None => return Ok(processed),
+                                Some((i, bucket)) => {
+                                    processed.push((i, process_bucket::<T>(bucket, options)?))
+                                }
+                            }
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect::<Result<Vec<_>, CommError>>()
+        })?;
+
+        let mut results = processed.into_iter().flatten().collect::<Vec<_>>();
+        results.sort_unstable_by_key(|(bucket, _)| *bucket);
+
+        let mut comm = Self {
+            pending: results.iter().map(|_| None).collect(),
+            results: results.into_iter().map(|(_, result)| result).collect(),
+            heads: BinaryHeap::new(),
+            failed: false,
+        };
+        for bucket in 0..comm.results.len() {
+            comm.pull(bucket)?;
+        }
+
+        Ok(comm)
+    }
+
+    fn pull(&mut self, bucket: usize) -> Result<(), CommError> {
+        let record = read_record(&mut self.results[bucket]).map_err(CommError::Temp)?;
+        if let Some((tag, position, bytes)) = record {
+            let line = T::from_bytes(bytes).unwrap();
+            let entry = match tag {
+                ONLY_LEFT => Entry::OnlyLeft(line),
+                ONLY_RIGHT => Entry::OnlyRight(line),
+                _ => Entry::Both(line),
+            };
+
+            self.heads
+                .push(Reverse((tag == ONLY_LEFT, position, bucket)));
+            self.pending[bucket] = Some(entry);
+        }
+
+        Ok(())
+    }
+
+    fn step(&mut self) -> Result<Option<Entry<T>>, CommError> {
+        let bucket = match self.heads.pop() {
+            None => return Ok(None),
+            Some(Reverse((_, _, bucket))) => bucket,
+        };
+
+        let entry = self.pending[bucket].take();
+        self.pull(bucket)?;
+        Ok(entry)
+    }
+}
+
+impl<T: Record> Iterator for PartitionedComm<T> {
+    type Item = Result<Entry<T>, CommError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        match self.step() {
+            Ok(entry) => entry.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn comm_partitioned(
+    first_filename: &str,
+    second_filename: &str,
+    options: &Options,
+    partitioning: &Partitioning,
+) -> Result<Totals, CommError> {
+    let entries = PartitionedComm::new(
+        open(first_filename)?,
+        open(second_filename)?,
+        options,
+        partitioning,
+    )?;
+    write_entries(&mut io::stdout().lock(), entries, options)
+}
@@ -0,0 +1,97 @@
+use crate::{CommError, Key, Options};
+use std::borrow::Cow;
+use std::io::BufRead;
+use std::mem;
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub trait Record: Sized {
+    fn from_bytes(bytes: Vec<u8>) -> Option<Self>;
+    fn as_bytes(&self) -> &[u8];
+    fn key<'a>(&'a self, key: &Key) -> Cow<'a, [u8]>;
+}
+
+impl Record for String {
+    fn from_bytes(bytes: Vec<u8>) -> Option<Self> {
+        String::from_utf8(bytes).ok()
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self.as_str().as_bytes()
+    }
+
+    fn key<'a>(&'a self, key: &Key) -> Cow<'a, [u8]> {
+        match key.extract(self) {
+            Cow::Borrowed(key) => Cow::Borrowed(key.as_bytes()),
+            Cow::Owned(key) => Cow::Owned(key.into_bytes()),
+        }
+    }
+}
+
+impl Record for Vec<u8> {
+    fn from_bytes(bytes: Vec<u8>) -> Option<Self> {
+        Some(bytes)
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn key<'a>(&'a self, key: &Key) -> Cow<'a, [u8]> {
+        key.extract_bytes(self)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) struct Records<R: BufRead> {
+    reader: R,
+    pub(crate) file: usize,
+    line: usize,
+    separator: u8,
+    strip_cr: bool,
+    buffer: Vec<u8>,
+}
+
+impl<R: BufRead> Records<R> {
+    pub(crate) fn new(reader: R, file: usize, options: &Options) -> Self {
+        Self {
+            reader,
+            file,
+            line: 0,
+            separator: options.separator,
+            strip_cr: options.strip_cr,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub(crate) fn read<T: Record>(&mut self) -> Result<Option<T>, CommError> {
+        self.buffer.clear();
+        let read = self
+            .reader
+            .read_until(self.separator, &mut self.buffer)
+            .map_err(|source| CommError::Read {
+                file: self.file,
+                source,
+            })?;
+        if read == 0 {
+            return Ok(None);
+        }
+
+        self.line += 1;
+        if self.buffer.last() == Some(&self.separator) {
+            self.buffer.pop();
+        }
+        if self.strip_cr && self.buffer.last() == Some(&b'\r') {
+            self.buffer.pop();
+        }
+
+        match T::from_bytes(mem::take(&mut self.buffer)) {
+            Some(record) => Ok(Some(record)),
+            None => Err(CommError::InvalidUtf8 {
+                file: self.file,
+                line: self.line,
+            }),
+        }
+    }
+}
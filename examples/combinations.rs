@@ -1,4 +1,4 @@
-use combinations::combinations;
+use combinations::{combinations, Combinations};
 
 fn main() {
     let arr = vec![1, 2, 3, 4];
@@ -7,4 +7,10 @@ fn main() {
     let result = combinations(&arr, k);
 
     println!("Combinations: {:?}", result);
+
+    let mut lazy = Combinations::new(&arr, k);
+    println!("Count: {}", lazy.size_hint().0);
+    while let Some(combination) = lazy.next_slice() {
+        println!("{:?}", combination);
+    }
 }
@@ -23,5 +23,7 @@ fn main() {
     println!();
     println!("[COMM]");
 
-    comm::comm(&args[1], &args[2]);
+    if let Err(err) = comm::comm(&args[1], &args[2]) {
+        eprintln!("comm: {}", err);
+    }
 }
@@ -9,4 +9,12 @@ fn main() {
         "{}",
         prefix::longest_common_prefix(vec!["hi!✊ Ìha", "hi!✊ Ýha"])
     );
+    println!(
+        "{}",
+        prefix::longest_common_prefix_graphemes(&["hi!✊ Ìha", "hi!✊ Ýha"])
+    );
+    println!(
+        "{}",
+        prefix::longest_common_path_prefix(&["/usr/lib", "/usr/libexec"]).display()
+    );
 }
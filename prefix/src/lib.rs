@@ -1,5 +1,14 @@
 #![forbid(unsafe_code)]
 
+mod radix;
+mod substring;
+
+pub use radix::{Iter, RadixTree};
+pub use substring::longest_common_substring;
+
+use std::path::{Path, PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
+
 pub fn longest_common_prefix(strs: Vec<&str>) -> String {
     if strs.is_empty() {
         return String::new();
@@ -33,3 +42,93 @@ pub fn longest_common_prefix(strs: Vec<&str>) -> String {
 
     res.iter().collect()
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Compares bytes and then backs off to a char boundary, so the result is a
+// slice of the first string and nothing is allocated.
+pub fn longest_common_prefix_bytes<'a>(strs: &[&'a str]) -> &'a str {
+    let first = match strs.first() {
+        None => return "",
+        Some(first) => *first,
+    };
+
+    let mut len = strs[1..].iter().fold(first.len(), |len, str| {
+        first.as_bytes()[..len]
+            .iter()
+            .zip(str.as_bytes())
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    while !first.is_char_boundary(len) {
+        len -= 1;
+    }
+    &first[..len]
+}
+
+// Like `longest_common_prefix_bytes`, but from the end: the common suffix of
+// archive.tar.gz and backup.gz is .gz.
+pub fn longest_common_suffix<'a>(strs: &[&'a str]) -> &'a str {
+    let first = match strs.first() {
+        None => return "",
+        Some(first) => *first,
+    };
+
+    let len = strs[1..].iter().fold(first.len(), |len, str| {
+        first.as_bytes()[first.len() - len..]
+            .iter()
+            .rev()
+            .zip(str.as_bytes().iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    let mut start = first.len() - len;
+    while !first.is_char_boundary(start) {
+        start += 1;
+    }
+    &first[start..]
+}
+
+// Compares extended grapheme clusters, so a base letter is never split from
+// its combining marks and an emoji sequence is kept whole.
+pub fn longest_common_prefix_graphemes<'a>(strs: &[&'a str]) -> &'a str {
+    let first = match strs.first() {
+        None => return "",
+        Some(first) => *first,
+    };
+
+    let mut others = strs[1..]
+        .iter()
+        .map(|str| str.graphemes(true))
+        .collect::<Vec<_>>();
+    let mut len = 0;
+    for grapheme in first.graphemes(true) {
+        if !others
+            .iter_mut()
+            .all(|other| other.next() == Some(grapheme))
+        {
+            break;
+        }
+        len += grapheme.len();
+    }
+    &first[..len]
+}
+
+// Compares whole path components: the common prefix of /usr/lib and
+// /usr/libexec is /usr.
+pub fn longest_common_path_prefix<P: AsRef<Path>>(paths: &[P]) -> PathBuf {
+    let first = match paths.first() {
+        None => return PathBuf::new(),
+        Some(first) => first.as_ref(),
+    };
+
+    let len = paths[1..].iter().fold(usize::MAX, |len, path| {
+        first
+            .components()
+            .zip(path.as_ref().components())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    first.components().take(len).collect()
+}
@@ -0,0 +1,261 @@
+////////////////////////////////////////////////////////////////////////////////
+
+// The length in bytes of the common prefix of `a` and `b`, on a char boundary.
+fn common_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map_or(a.len().min(b.len()), |((i, _), _)| i)
+}
+
+fn first_char(str: &str) -> char {
+    str.chars().next().unwrap()
+}
+
+// Every node but the root has a non-empty label, and the labels of siblings
+// start with different chars, kept in sorted order.
+struct Node<V> {
+    label: String,
+    value: Option<V>,
+    children: Vec<Node<V>>,
+}
+
+impl<V> Node<V> {
+    fn new(label: &str, value: Option<V>) -> Self {
+        Self {
+            label: label.to_string(),
+            value,
+            children: Vec::new(),
+        }
+    }
+
+    fn child_position(&self, key: &str) -> Result<usize, usize> {
+        let c = first_char(key);
+        self.children
+            .binary_search_by(|child| first_char(&child.label).cmp(&c))
+    }
+
+    fn child(&self, key: &str) -> Option<&Node<V>> {
+        if key.is_empty() {
+            return None;
+        }
+        let i = self.child_position(key).ok()?;
+        Some(&self.children[i])
+    }
+
+    fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        if key.is_empty() {
+            return self.value.replace(value);
+        }
+
+        let i = match self.child_position(key) {
+            Ok(i) => i,
+            Err(i) => {
+                self.children.insert(i, Node::new(key, Some(value)));
+                return None;
+            }
+        };
+
+        let child = &mut self.children[i];
+        let len = common_len(&child.label, key);
+        if len < child.label.len() {
+            // Split the edge where the key leaves it.
+            let rest = child.label.split_off(len);
+            let mut lower = Node::new(&rest, child.value.take());
+            lower.children = std::mem::take(&mut child.children);
+            child.children.push(lower);
+        }
+        child.insert(&key[len..], value)
+    }
+
+    fn remove(&mut self, key: &str) -> Option<V> {
+        if key.is_empty() {
+            return self.value.take();
+        }
+
+        let i = self.child_position(key).ok()?;
+        let child = &mut self.children[i];
+        let rest = key.strip_prefix(child.label.as_str())?;
+        let removed = child.remove(rest)?;
+
+        // Drop nodes left without a value and merge chains of single children.
+        if child.value.is_none() {
+            match child.children.len() {
+                0 => {
+                    self.children.remove(i);
+                }
+                1 => {
+                    let only = child.children.pop().unwrap();
+                    child.label.push_str(&only.label);
+                    child.value = only.value;
+                    child.children = only.children;
+                }
+                _ => {}
+            }
+        }
+        Some(removed)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A map from strings that stores shared prefixes once, for prefix lookups
+// like autocompletion and longest-prefix-match routing.
+pub struct RadixTree<V = ()> {
+    root: Node<V>,
+    len: usize,
+}
+
+impl<V> Default for RadixTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> RadixTree<V> {
+    pub fn new() -> Self {
+        Self {
+            root: Node::new("", None),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let old = self.root.insert(key, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let removed = self.root.remove(key);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V> {
+        let mut node = &self.root;
+        let mut key = key;
+        while !key.is_empty() {
+            node = node.child(key)?;
+            key = key.strip_prefix(node.label.as_str())?;
+        }
+        node.value.as_ref()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    // The longest stored key that is a prefix of `query`, as a slice of it.
+    pub fn longest_prefix_match<'q>(&self, query: &'q str) -> Option<(&'q str, &V)> {
+        let mut node = &self.root;
+        let mut matched = 0;
+        let mut best = node.value.as_ref().map(|value| (0, value));
+        while let Some(child) = node.child(&query[matched..]) {
+            if !query[matched..].starts_with(child.label.as_str()) {
+                break;
+            }
+            node = child;
+            matched += child.label.len();
+            if let Some(value) = &node.value {
+                best = Some((matched, value));
+            }
+        }
+        best.map(|(len, value)| (&query[..len], value))
+    }
+
+    // The keys starting with `prefix` and their values, in sorted order.
+    pub fn with_prefix(&self, prefix: &str) -> Iter<'_, V> {
+        let mut node = &self.root;
+        let mut key = String::new();
+        let mut rest = prefix;
+        while !rest.is_empty() {
+            let child = match node.child(rest) {
+                None => return Iter::empty(),
+                Some(child) => child,
+            };
+
+            if let Some(tail) = rest.strip_prefix(child.label.as_str()) {
+                rest = tail;
+            } else if child.label.starts_with(rest) {
+                rest = "";
+            } else {
+                return Iter::empty();
+            }
+            key.push_str(&child.label);
+            node = child;
+        }
+
+        key.truncate(key.len() - node.label.len());
+        Iter {
+            stack: vec![(key.len(), node)],
+            key,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, V> {
+        self.with_prefix("")
+    }
+
+    // The longest common prefix of all the keys, found by following the
+    // chain of single children from the root.
+    pub fn common_prefix(&self) -> String {
+        let mut prefix = String::new();
+        let mut node = &self.root;
+        while node.value.is_none() && node.children.len() == 1 {
+            node = &node.children[0];
+            prefix.push_str(&node.label);
+        }
+        prefix
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Walks the tree depth first. A key sorts before its extensions and children
+// are ordered by their first char, so the keys come out sorted.
+pub struct Iter<'a, V> {
+    // Nodes to visit along with the length of their parent's key.
+    stack: Vec<(usize, &'a Node<V>)>,
+    key: String,
+}
+
+impl<'a, V> Iter<'a, V> {
+    fn empty() -> Self {
+        Self {
+            stack: Vec::new(),
+            key: String::new(),
+        }
+    }
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((parent, node)) = self.stack.pop() {
+            self.key.truncate(parent);
+            self.key.push_str(&node.label);
+            let len = self.key.len();
+            self.stack
+                .extend(node.children.iter().rev().map(|child| (len, child)));
+
+            if let Some(value) = &node.value {
+                return Some((self.key.clone(), value));
+            }
+        }
+        None
+    }
+}
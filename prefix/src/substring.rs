@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Sorts the suffixes by prefix doubling: after the round with step k, ranks
+// order the suffixes by their first 2k symbols.
+fn suffix_array(text: &[u32]) -> Vec<usize> {
+    let n = text.len();
+    let mut sa = (0..n).collect::<Vec<_>>();
+    if n == 0 {
+        return sa;
+    }
+
+    let mut rank = text.iter().map(|&c| c as usize).collect::<Vec<_>>();
+    let mut next = vec![0; n];
+    let mut k = 1;
+    loop {
+        let key = |i: usize| (rank[i], rank.get(i + k).map_or(0, |r| r + 1));
+        sa.sort_unstable_by_key(|&i| key(i));
+        next[sa[0]] = 0;
+        for w in 1..n {
+            next[sa[w]] = next[sa[w - 1]] + usize::from(key(sa[w - 1]) != key(sa[w]));
+        }
+
+        std::mem::swap(&mut rank, &mut next);
+        if rank[sa[n - 1]] == n - 1 {
+            break;
+        }
+        k *= 2;
+    }
+    sa
+}
+
+// lcp[w] is the length of the common prefix of the suffixes sa[w - 1] and
+// sa[w], by Kasai's algorithm.
+fn lcp_array(text: &[u32], sa: &[usize]) -> Vec<usize> {
+    let n = text.len();
+    let mut rank = vec![0; n];
+    for (w, &i) in sa.iter().enumerate() {
+        rank[i] = w;
+    }
+
+    let mut lcp = vec![0; n];
+    let mut h: usize = 0;
+    for i in 0..n {
+        if rank[i] == 0 {
+            h = 0;
+            continue;
+        }
+        let j = sa[rank[i] - 1];
+        while i + h < n && j + h < n && text[i + h] == text[j + h] {
+            h += 1;
+        }
+        lcp[rank[i]] = h;
+        h = h.saturating_sub(1);
+    }
+    lcp
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The longest string that occurs in every input, as a slice of one of them.
+// All inputs are joined into one text, each followed by its own separator,
+// and the answer is the best common prefix of a run of sorted suffixes that
+// starts in every input. O(L log^2 L) for a total length of L chars.
+pub fn longest_common_substring<'a>(strs: &[&'a str]) -> &'a str {
+    match strs.len() {
+        0 => return "",
+        1 => return strs[0],
+        _ => {}
+    }
+
+    // Separators are 0..count and sort before every char.
+    let count = strs.len() as u32;
+    let mut text = Vec::new();
+    let mut owner = Vec::new();
+    let mut offset = Vec::new();
+    for (s, str) in strs.iter().enumerate() {
+        for (i, c) in str.char_indices() {
+            text.push(c as u32 + count);
+            owner.push(s);
+            offset.push(i);
+        }
+        text.push(s as u32);
+        owner.push(s);
+        offset.push(str.len());
+    }
+
+    let sa = suffix_array(&text);
+    let lcp = lcp_array(&text, &sa);
+
+    // A separator is unique, so no common prefix can reach past one.
+    let mut seen = vec![0; strs.len()];
+    let mut covered = 0;
+    let mut minimum = VecDeque::new();
+    let (mut best, mut best_start) = (0, 0);
+    let mut left = 0;
+    for right in 0..sa.len() {
+        if text[sa[right]] >= count {
+            let s = owner[sa[right]];
+            seen[s] += 1;
+            if seen[s] == 1 {
+                covered += 1;
+            }
+        }
+        if right > 0 {
+            while minimum.back().is_some_and(|&w| lcp[w] >= lcp[right]) {
+                minimum.pop_back();
+            }
+            minimum.push_back(right);
+        }
+
+        while covered == strs.len() {
+            while minimum.front().is_some_and(|&w| w <= left) {
+                minimum.pop_front();
+            }
+            let shared = minimum.front().map_or(0, |&w| lcp[w]);
+            if shared > best {
+                best = shared;
+                best_start = sa[left];
+            }
+
+            if text[sa[left]] >= count {
+                let s = owner[sa[left]];
+                seen[s] -= 1;
+                if seen[s] == 0 {
+                    covered -= 1;
+                }
+            }
+            left += 1;
+        }
+    }
+
+    // The answer never includes a separator, so its end is in the same input.
+    let str = strs[owner[best_start]];
+    &str[offset[best_start]..offset[best_start + best]]
+}